@@ -0,0 +1,164 @@
+use crate::utils::Alphabet;
+
+/// Relative letter frequencies (percent) of English text, in `a..z` order.
+pub const ENGLISH_FREQUENCIES: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+/// Approximate relative letter frequencies (percent) of Modern Standard Arabic, in the order
+/// of `Alphabet::arabic()`, with hamza and alef variants folded into their base letters.
+pub const ARABIC_FREQUENCIES: [f64; 28] = [
+    17.44, 2.77, 5.37, 0.47, 1.44, 2.26, 1.23, 3.28, 0.94, 5.49, 0.72, 3.00, 0.99, 1.83, 0.74,
+    1.39, 0.20, 3.01, 0.86, 3.55, 2.00, 1.90, 12.49, 7.47, 4.36, 1.09, 4.94, 8.78,
+];
+
+/// Returns the built-in reference table whose symbols match `alphabet`, if any.
+pub fn reference_frequencies(alphabet: &Alphabet) -> Option<&'static [f64]> {
+    if alphabet.symbols() == Alphabet::english().symbols() {
+        Some(&ENGLISH_FREQUENCIES)
+    } else if alphabet.symbols() == Alphabet::arabic().symbols() {
+        Some(&ARABIC_FREQUENCIES)
+    } else {
+        None
+    }
+}
+
+pub fn letter_counts(text: &str, alphabet: &Alphabet) -> Vec<usize> {
+    let mut counts = vec![0; alphabet.len()];
+    for idx in text.chars().filter_map(|c| alphabet.index_of(c)) {
+        counts[idx] += 1;
+    }
+    counts
+}
+
+/// Relative frequency (percent) of every alphabet symbol in `text`.
+pub fn letter_frequencies(text: &str, alphabet: &Alphabet) -> Vec<f64> {
+    let counts = letter_counts(text, alphabet);
+    let total: usize = counts.iter().sum();
+    if total == 0 {
+        return vec![0.0; alphabet.len()];
+    }
+    counts
+        .iter()
+        .map(|&count| count as f64 * 100.0 / total as f64)
+        .collect()
+}
+
+/// Text bar chart of `letter_frequencies`, one line per symbol, with the most
+/// frequent symbol drawn `width` characters wide.
+pub fn frequency_histogram(text: &str, alphabet: &Alphabet, width: usize) -> String {
+    let frequencies = letter_frequencies(text, alphabet);
+    let max = frequencies.iter().copied().fold(0.0, f64::max);
+    let mut histogram = String::new();
+    for (idx, &frequency) in frequencies.iter().enumerate() {
+        let bar = if max > 0.0 {
+            (frequency / max * width as f64).round() as usize
+        } else {
+            0
+        };
+        histogram.push_str(&format!(
+            "{} |{:<width$}| {frequency:5.2}%\n",
+            alphabet.symbol(idx),
+            "#".repeat(bar)
+        ));
+    }
+    histogram
+}
+
+/// Pearson's chi-squared statistic of observed `counts` against `expected` percentages.
+///
+/// Lower is closer to the reference language.
+pub fn chi_squared(counts: &[usize], expected: &[f64]) -> f64 {
+    let total: usize = counts.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    counts
+        .iter()
+        .zip(expected)
+        .filter(|&(_, &percent)| percent > 0.0)
+        .map(|(&count, &percent)| {
+            let expected = total as f64 * percent / 100.0;
+            let diff = count as f64 - expected;
+            diff * diff / expected
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_tables_sum_to_100() {
+        let english: f64 = ENGLISH_FREQUENCIES.iter().sum();
+        let arabic: f64 = ARABIC_FREQUENCIES.iter().sum();
+        assert!((english - 100.0).abs() < 0.5);
+        assert!((arabic - 100.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_reference_frequencies() {
+        assert_eq!(
+            reference_frequencies(&Alphabet::english()),
+            Some(&ENGLISH_FREQUENCIES[..])
+        );
+        assert_eq!(
+            reference_frequencies(&Alphabet::arabic()),
+            Some(&ARABIC_FREQUENCIES[..])
+        );
+        assert_eq!(reference_frequencies(&Alphabet::english_merged_ij()), None);
+    }
+
+    #[test]
+    fn test_letter_counts() {
+        let counts = letter_counts("Hello, World!", &Alphabet::english());
+        assert_eq!(counts[7], 1); // h
+        assert_eq!(counts[11], 3); // l
+        assert_eq!(counts[14], 2); // o
+        assert_eq!(counts.iter().sum::<usize>(), 10);
+    }
+
+    #[test]
+    fn test_letter_frequencies_arabic() {
+        let alphabet = Alphabet::arabic();
+
+        // "أ" is folded into "ا", spaces are ignored
+        let frequencies = letter_frequencies("أنا ما", &alphabet);
+        assert_eq!(frequencies[0], 60.0);
+        assert_eq!(frequencies[alphabet.index_of('ن').unwrap()], 20.0);
+        assert_eq!(frequencies[alphabet.index_of('م').unwrap()], 20.0);
+    }
+
+    #[test]
+    fn test_letter_frequencies_empty() {
+        let frequencies = letter_frequencies("123", &Alphabet::english());
+        assert!(frequencies.iter().all(|&f| f == 0.0));
+    }
+
+    #[test]
+    fn test_chi_squared() {
+        let alphabet = Alphabet::english();
+        let english = letter_counts("the quick brown fox jumps over the lazy dog", &alphabet);
+        let shifted = letter_counts("wkh txlfn eurzq ira mxpsv ryhu wkh odcb grj", &alphabet);
+        assert!(
+            chi_squared(&english, &ENGLISH_FREQUENCIES)
+                < chi_squared(&shifted, &ENGLISH_FREQUENCIES)
+        );
+        assert_eq!(chi_squared(&[0; 26], &ENGLISH_FREQUENCIES), 0.0);
+    }
+
+    #[test]
+    fn test_frequency_histogram() {
+        let alphabet = Alphabet::new("abc").unwrap();
+        assert_eq!(
+            frequency_histogram("aaab", &alphabet, 6),
+            "a |######| 75.00%\nb |##    | 25.00%\nc |      |  0.00%\n"
+        );
+        assert_eq!(
+            frequency_histogram("", &alphabet, 2),
+            "a |  |  0.00%\nb |  |  0.00%\nc |  |  0.00%\n"
+        );
+    }
+}
@@ -0,0 +1,7 @@
+pub mod frequency;
+pub mod ngram;
+pub mod statistics;
+
+pub use frequency::*;
+pub use ngram::NGramScorer;
+pub use statistics::*;
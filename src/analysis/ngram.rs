@@ -0,0 +1,338 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::PolygraphiaError;
+use crate::utils::Alphabet;
+
+const ENGLISH_MONOGRAMS: &str = include_str!("data/english_monograms.txt");
+const ENGLISH_BIGRAMS: &str = include_str!("data/english_bigrams.txt");
+const ENGLISH_TRIGRAMS: &str = include_str!("data/english_trigrams.txt");
+const ENGLISH_QUADGRAMS: &str = include_str!("data/english_quadgrams.txt");
+
+/// Longest n-grams a model may use; the table holds `alphabet.len()^n` scores.
+pub const MAX_NGRAM_LEN: usize = 5;
+
+/// Most scores a table may hold, 128 MiB of them: enough for 5-grams over 26
+/// letters, but not over much larger alphabets.
+pub const MAX_NGRAM_TABLE: usize = 1 << 24;
+
+/// Fitness function that sums the log10 probabilities of every n-gram in a text.
+///
+/// Scores are negative; higher (closer to zero per letter) means more language-like.
+#[derive(Debug, Clone)]
+pub struct NGramScorer {
+    n: usize,
+    alphabet: Alphabet,
+    log_probs: Vec<f64>,
+    floor: f64,
+}
+
+impl NGramScorer {
+    /// English model of order `n` (1 to 4) built from the embedded count tables.
+    pub fn english(n: usize) -> Result<Self, PolygraphiaError> {
+        let counts = match n {
+            1 => ENGLISH_MONOGRAMS,
+            2 => ENGLISH_BIGRAMS,
+            3 => ENGLISH_TRIGRAMS,
+            4 => ENGLISH_QUADGRAMS,
+            _ => {
+                return Err(PolygraphiaError::InvalidInput(format!(
+                    "No built-in English table for {n}-grams (1 to 4 available)"
+                )));
+            }
+        };
+        Self::from_counts(counts, Alphabet::english())
+    }
+
+    pub fn english_monograms() -> Self {
+        Self::english(1).expect("embedded monogram table is well-formed")
+    }
+
+    pub fn english_bigrams() -> Self {
+        Self::english(2).expect("embedded bigram table is well-formed")
+    }
+
+    pub fn english_trigrams() -> Self {
+        Self::english(3).expect("embedded trigram table is well-formed")
+    }
+
+    pub fn english_quadgrams() -> Self {
+        Self::english(4).expect("embedded quadgram table is well-formed")
+    }
+
+    /// Loads a count file in the format accepted by `from_counts`.
+    pub fn from_file(path: impl AsRef<Path>, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        let path = path.as_ref();
+        let counts = fs::read_to_string(path).map_err(|e| {
+            PolygraphiaError::InvalidInput(format!("Cannot read {}: {e}", path.display()))
+        })?;
+        Self::from_counts(&counts, alphabet)
+    }
+
+    /// Builds a model from lines of `NGRAM COUNT`, e.g. `TION 13168`.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, and counts for an
+    /// n-gram listed twice are added together. Every n-gram must have the same
+    /// length, at most `MAX_NGRAM_LEN`, and consist of symbols from `alphabet`;
+    /// the table may have at most `MAX_NGRAM_TABLE` slots.
+    pub fn from_counts(counts: &str, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        let mut table: Vec<u64> = Vec::new();
+        let mut n = 0;
+        let mut total: u64 = 0;
+        for (line_no, line) in counts.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = || {
+                PolygraphiaError::InvalidInput(format!(
+                    "Line {}: expected 'NGRAM COUNT', got '{line}'",
+                    line_no + 1
+                ))
+            };
+            let (gram, count) = line.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            let count: u64 = count.trim().parse().map_err(|_| invalid())?;
+            let indices: Option<Vec<u8>> = gram
+                .chars()
+                .map(|c| alphabet.index_of(c).map(|idx| idx as u8))
+                .collect();
+            let indices = indices.ok_or_else(invalid)?;
+            if n == 0 {
+                n = indices.len();
+                if n > MAX_NGRAM_LEN {
+                    return Err(PolygraphiaError::InvalidInput(format!(
+                        "Line {}: {n}-grams are longer than the supported {MAX_NGRAM_LEN}",
+                        line_no + 1
+                    )));
+                }
+                let size = alphabet
+                    .len()
+                    .checked_pow(n as u32)
+                    .filter(|&size| size <= MAX_NGRAM_TABLE)
+                    .ok_or_else(|| {
+                        PolygraphiaError::InvalidInput(format!(
+                            "A {n}-gram table over {} symbols has more than {MAX_NGRAM_TABLE} slots",
+                            alphabet.len()
+                        ))
+                    })?;
+                table = vec![0; size];
+            } else if indices.len() != n {
+                return Err(invalid());
+            }
+            let slot = &mut table[Self::slot(alphabet.len(), &indices)];
+            let too_large = || {
+                PolygraphiaError::InvalidInput(format!(
+                    "Line {}: counts add up to more than {}",
+                    line_no + 1,
+                    u64::MAX
+                ))
+            };
+            *slot = slot.checked_add(count).ok_or_else(too_large)?;
+            total = total.checked_add(count).ok_or_else(too_large)?;
+        }
+        if total == 0 {
+            return Err(PolygraphiaError::InvalidInput(
+                "N-gram table has no counts".to_string(),
+            ));
+        }
+
+        let floor = (0.01 / total as f64).log10();
+        let log_probs = table
+            .into_iter()
+            .map(|count| {
+                if count > 0 {
+                    (count as f64 / total as f64).log10()
+                } else {
+                    floor
+                }
+            })
+            .collect();
+        Ok(NGramScorer {
+            n,
+            alphabet,
+            log_probs,
+            floor,
+        })
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    /// Log10 probability assigned to n-grams never seen in the table.
+    pub fn floor(&self) -> f64 {
+        self.floor
+    }
+
+    /// Scores the letters of `text`, skipping anything outside the alphabet.
+    pub fn score(&self, text: &str) -> f64 {
+        let indices: Vec<u8> = text
+            .chars()
+            .filter_map(|c| self.alphabet.index_of(c))
+            .map(|idx| idx as u8)
+            .collect();
+        self.score_indices(&indices)
+    }
+
+    /// Scores a text already converted to alphabet indices.
+    pub fn score_indices(&self, indices: &[u8]) -> f64 {
+        indices
+            .windows(self.n)
+            .map(|gram| self.log_probs[Self::slot(self.alphabet.len(), gram)])
+            .sum()
+    }
+
+    /// Scores only the n-grams of `indices` that overlap any of `positions`.
+    ///
+    /// Each n-gram is counted once, however many of its positions are listed.
+    pub fn score_positions(&self, indices: &[u8], positions: &[usize]) -> f64 {
+        if indices.len() < self.n {
+            return 0.0;
+        }
+        let last_start = indices.len() - self.n;
+        let mut starts: Vec<usize> = positions
+            .iter()
+            .filter(|&&pos| pos < indices.len())
+            .flat_map(|&pos| pos.saturating_sub(self.n - 1)..=pos.min(last_start))
+            .collect();
+        starts.sort_unstable();
+        starts.dedup();
+        starts
+            .into_iter()
+            .map(|start| {
+                self.log_probs[Self::slot(self.alphabet.len(), &indices[start..start + self.n])]
+            })
+            .sum()
+    }
+
+    /// Updates `score` (the score of `before`) for `after`, which differs from
+    /// `before` only at `positions`. Costs O(positions) rather than O(text).
+    pub fn rescore(&self, score: f64, before: &[u8], after: &[u8], positions: &[usize]) -> f64 {
+        score - self.score_positions(before, positions) + self.score_positions(after, positions)
+    }
+
+    fn slot(base: usize, indices: &[u8]) -> usize {
+        indices
+            .iter()
+            .fold(0, |slot, &idx| slot * base + idx as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_english_quadgrams() {
+        let scorer = NGramScorer::english_quadgrams();
+        assert_eq!(scorer.n(), 4);
+        let english = scorer.score("the quick brown fox jumps over the lazy dog");
+        let shuffled = scorer.score("eht kciuq nworb xof spmuj revo eht yzal god");
+        let gibberish = scorer.score("qzxj vkwq pzfh xjqz vbnm qwzx kjhq zxvp qqqq");
+        assert!(english > shuffled);
+        assert!(shuffled > gibberish);
+    }
+
+    #[test]
+    fn test_english_tables() {
+        for n in 1..=4 {
+            let scorer = NGramScorer::english(n).unwrap();
+            assert_eq!(scorer.n(), n);
+            assert!(scorer.score("attack at dawn") > scorer.score("xqzvjk qz kwpj"));
+        }
+        assert!(NGramScorer::english(0).is_err());
+        assert!(NGramScorer::english(5).is_err());
+        assert_eq!(NGramScorer::english_bigrams().n(), 2);
+    }
+
+    #[test]
+    fn test_rescore_matches_full_score() {
+        let scorer = NGramScorer::english_trigrams();
+        let alphabet = Alphabet::english();
+        let to_indices = |text: &str| -> Vec<u8> {
+            text.chars()
+                .map(|c| alphabet.index_of(c).unwrap() as u8)
+                .collect()
+        };
+        let before = to_indices("meetmeafterthetogaparty");
+        let mut after = before.clone();
+        // Swap every 'e' and 't', as a substitution solver would
+        let positions: Vec<usize> = (0..after.len())
+            .filter(|&i| after[i] == 4 || after[i] == 19)
+            .collect();
+        for &i in &positions {
+            after[i] = if after[i] == 4 { 19 } else { 4 };
+        }
+
+        let score = scorer.score_indices(&before);
+        let rescored = scorer.rescore(score, &before, &after, &positions);
+        assert!((rescored - scorer.score_indices(&after)).abs() < 1e-9);
+
+        assert_eq!(scorer.score_positions(&before, &[]), 0.0);
+        assert_eq!(
+            scorer.score_positions(&before, &(0..before.len()).collect::<Vec<_>>()),
+            score
+        );
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join("polygraphia_ngram_test.txt");
+        fs::write(&path, "TH 3\nHE 1\n").unwrap();
+        let scorer = NGramScorer::from_file(&path, Alphabet::english()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(scorer.n(), 2);
+        assert!((scorer.score("the") - (0.75f64 * 0.25).log10()).abs() < 1e-12);
+
+        assert!(NGramScorer::from_file(&path, Alphabet::english()).is_err());
+    }
+
+    #[test]
+    fn test_score_ignores_layout() {
+        let scorer = NGramScorer::english_quadgrams();
+        assert_eq!(scorer.score("Hello, World!"), scorer.score("helloworld"));
+        assert_eq!(scorer.score("abc"), 0.0);
+    }
+
+    #[test]
+    fn test_from_counts() {
+        let counts = "# bigrams\nAB 3\n\nBA 1\n";
+        let scorer = NGramScorer::from_counts(counts, Alphabet::new("ab").unwrap()).unwrap();
+        assert_eq!(scorer.n(), 2);
+        assert!((scorer.score("ab") - 0.75f64.log10()).abs() < 1e-12);
+        assert!((scorer.score("aba") - (0.75f64 * 0.25).log10()).abs() < 1e-12);
+        assert_eq!(scorer.score("aa"), scorer.floor());
+    }
+
+    #[test]
+    fn test_from_counts_invalid() {
+        let alphabet = Alphabet::english();
+        assert!(NGramScorer::from_counts("", alphabet.clone()).is_err());
+        assert!(NGramScorer::from_counts("AB x", alphabet.clone()).is_err());
+        assert!(NGramScorer::from_counts("AB 1\nABC 2", alphabet.clone()).is_err());
+        assert!(NGramScorer::from_counts("A1 4", alphabet.clone()).is_err());
+        assert!(NGramScorer::from_counts("ABCDEFGHIJ 1", alphabet.clone()).is_err());
+        assert!(NGramScorer::from_counts("ABCDE 1", alphabet.clone()).is_ok());
+        let overflow = format!("AB {}\nBA 1", u64::MAX);
+        assert!(NGramScorer::from_counts(&overflow, alphabet).is_err());
+
+        // 5-grams over 64 symbols would need 2^30 slots
+        let large = Alphabet::new(&('\u{4e00}'..'\u{4e40}').collect::<String>()).unwrap();
+        assert_eq!(large.len(), 64);
+        assert!(
+            NGramScorer::from_counts("\u{4e00}\u{4e01}\u{4e02}\u{4e03}\u{4e04} 1", large).is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_counts_adds_duplicates() {
+        let alphabet = Alphabet::english();
+        let twice = NGramScorer::from_counts("AB 1\nBA 2\nAB 1", alphabet.clone()).unwrap();
+        let once = NGramScorer::from_counts("AB 2\nBA 2", alphabet).unwrap();
+        assert_eq!(twice.score("ABA"), once.score("ABA"));
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use crate::analysis::letter_counts;
+use crate::utils::Alphabet;
+
+/// Probability that two letters drawn from `text` without replacement are equal.
+///
+/// Around 0.066 for English and 1/26 ≈ 0.038 for uniformly random letters.
+pub fn index_of_coincidence(text: &str, alphabet: &Alphabet) -> f64 {
+    let counts = letter_counts(text, alphabet);
+    let total: usize = counts.iter().sum();
+    if total < 2 {
+        return 0.0;
+    }
+    let matches: usize = counts.iter().map(|&n| n * n.saturating_sub(1)).sum();
+    matches as f64 / (total * (total - 1)) as f64
+}
+
+/// Index of coincidence expected from a reference table given in percent.
+pub fn expected_index_of_coincidence(reference: &[f64]) -> f64 {
+    reference.iter().map(|p| (p / 100.0) * (p / 100.0)).sum()
+}
+
+/// Shannon entropy of the letter distribution of `text`, in bits per letter.
+pub fn entropy(text: &str, alphabet: &Alphabet) -> f64 {
+    let counts = letter_counts(text, alphabet);
+    let total: usize = counts.iter().sum();
+    counts
+        .iter()
+        .filter(|&&n| n > 0)
+        .map(|&n| {
+            let p = n as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Counts every run of `n` consecutive letters, ignoring non-letters in between.
+///
+/// Letters are mapped to their canonical symbols as the ciphers see them, so
+/// `"I"`, `"i"` and `"J"` all count as `i` under `Alphabet::english_merged_ij()`.
+pub fn ngram_counts(text: &str, alphabet: &Alphabet, n: usize) -> BTreeMap<String, usize> {
+    let letters: Vec<char> = text
+        .chars()
+        .filter_map(|c| alphabet.index_of(c))
+        .map(|idx| alphabet.symbol(idx))
+        .collect();
+    let mut counts = BTreeMap::new();
+    if n == 0 {
+        return counts;
+    }
+    for gram in letters.windows(n) {
+        *counts.entry(gram.iter().collect()).or_insert(0) += 1;
+    }
+    counts
+}
+
+pub fn bigram_counts(text: &str, alphabet: &Alphabet) -> BTreeMap<String, usize> {
+    ngram_counts(text, alphabet, 2)
+}
+
+pub fn trigram_counts(text: &str, alphabet: &Alphabet) -> BTreeMap<String, usize> {
+    ngram_counts(text, alphabet, 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{ARABIC_FREQUENCIES, ENGLISH_FREQUENCIES};
+
+    const ENGLISH: &str = "It was the best of times, it was the worst of times, it was the age \
+        of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of Light, it was the season of Darkness";
+
+    #[test]
+    fn test_index_of_coincidence() {
+        let alphabet = Alphabet::english();
+        let ioc = index_of_coincidence(ENGLISH, &alphabet);
+        assert!(ioc > 0.055 && ioc < 0.08, "{ioc}");
+
+        let uniform: String = ('a'..='z').cycle().take(26 * 20).collect();
+        let ioc = index_of_coincidence(&uniform, &alphabet);
+        assert!((ioc - 19.0 / 519.0).abs() < 1e-12);
+
+        assert_eq!(index_of_coincidence("aaaa", &alphabet), 1.0);
+        assert_eq!(index_of_coincidence("a", &alphabet), 0.0);
+    }
+
+    #[test]
+    fn test_expected_index_of_coincidence() {
+        let english = expected_index_of_coincidence(&ENGLISH_FREQUENCIES);
+        assert!((english - 0.0655).abs() < 0.001, "{english}");
+        let arabic = expected_index_of_coincidence(&ARABIC_FREQUENCIES);
+        assert!(arabic > 1.0 / 28.0);
+    }
+
+    #[test]
+    fn test_entropy() {
+        let alphabet = Alphabet::english();
+        assert_eq!(entropy("aaaa", &alphabet), 0.0);
+        assert_eq!(entropy("abab", &alphabet), 1.0);
+        assert_eq!(entropy("ab cd!", &alphabet), 2.0);
+        assert_eq!(entropy("", &alphabet), 0.0);
+
+        let english = entropy(ENGLISH, &alphabet);
+        assert!(english > 3.8 && english < 4.3, "{english}");
+    }
+
+    #[test]
+    fn test_ngram_counts() {
+        let alphabet = Alphabet::english();
+        let bigrams = bigram_counts("The, then!", &alphabet);
+        assert_eq!(bigrams["th"], 2);
+        assert_eq!(bigrams["he"], 2);
+        assert_eq!(bigrams["et"], 1);
+        assert_eq!(bigrams["en"], 1);
+        assert_eq!(bigrams.values().sum::<usize>(), 6);
+
+        let trigrams = trigram_counts("the then", &alphabet);
+        assert_eq!(trigrams["the"], 2);
+        assert_eq!(trigrams.len(), 4);
+
+        assert!(ngram_counts("abc", &alphabet, 0).is_empty());
+        assert!(ngram_counts("ab", &alphabet, 3).is_empty());
+    }
+
+    #[test]
+    fn test_ngram_counts_normalization() {
+        let bigrams = bigram_counts("JIJI", &Alphabet::english_merged_ij());
+        assert_eq!(bigrams.len(), 1);
+        assert_eq!(bigrams["ii"], 3);
+    }
+}
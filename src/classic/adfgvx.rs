@@ -0,0 +1,204 @@
+use crate::classic::ColumnarTransposition;
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::{Alphabet, KeyGenerator, PolybiusSquare, TextMode};
+
+const ADFGX_LABELS: [char; 5] = ['A', 'D', 'F', 'G', 'X'];
+const ADFGVX_LABELS: [char; 6] = ['A', 'D', 'F', 'G', 'V', 'X'];
+const ADFGVX_SYMBOLS: &str = "abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// The ADFGX and ADFGVX field ciphers: each letter becomes the labels of its
+/// row and column in a Polybius square, and the labels are then mixed by a
+/// columnar transposition.
+///
+/// The ciphertext is made of labels only, so the layout of the plaintext is
+/// not kept and decryption returns lowercase letters without separators.
+#[derive(Debug, Clone)]
+pub struct Adfgvx {
+    square: PolybiusSquare,
+    transposition: ColumnarTransposition,
+    labels: &'static [char],
+}
+
+impl Adfgvx {
+    /// ADFGVX: a 6x6 square of letters and digits.
+    pub fn new(square_key: &str, transposition_key: &str) -> Result<Self, PolygraphiaError> {
+        let alphabet = Alphabet::new(ADFGVX_SYMBOLS).expect("ADFGVX alphabet is valid");
+        Self::build(square_key, transposition_key, alphabet, &ADFGVX_LABELS)
+    }
+
+    /// ADFGX: a 5x5 square with `j` merged into `i`.
+    pub fn adfgx(square_key: &str, transposition_key: &str) -> Result<Self, PolygraphiaError> {
+        Self::build(
+            square_key,
+            transposition_key,
+            Alphabet::english_merged_ij(),
+            &ADFGX_LABELS,
+        )
+    }
+
+    pub fn random(width: usize) -> Result<Self, PolygraphiaError> {
+        Self::random_with(width, &mut KeyGenerator::new())
+    }
+
+    /// ADFGVX with a random square and a transposition key of `width` letters.
+    pub fn random_with(
+        width: usize,
+        generator: &mut KeyGenerator,
+    ) -> Result<Self, PolygraphiaError> {
+        let alphabet = Alphabet::new(ADFGVX_SYMBOLS).expect("ADFGVX alphabet is valid");
+        let square_key = generator.permutation(&alphabet);
+        let transposition =
+            ColumnarTransposition::random_with(&[width], generator, Alphabet::default())?;
+        Self::build(
+            &square_key,
+            &transposition.keys()[0],
+            alphabet,
+            &ADFGVX_LABELS,
+        )
+    }
+
+    pub fn square(&self) -> &PolybiusSquare {
+        &self.square
+    }
+
+    pub fn square_key(&self) -> &str {
+        self.square.key()
+    }
+
+    pub fn transposition_key(&self) -> &str {
+        &self.transposition.keys()[0]
+    }
+
+    fn build(
+        square_key: &str,
+        transposition_key: &str,
+        alphabet: Alphabet,
+        labels: &'static [char],
+    ) -> Result<Self, PolygraphiaError> {
+        let side = labels.len();
+        let square = PolybiusSquare::with_shape(square_key, alphabet, side, side)?;
+        let mut transposition = ColumnarTransposition::new(transposition_key)?;
+        transposition.set_mode(TextMode::AlphaOnly);
+        Ok(Adfgvx {
+            square,
+            transposition,
+            labels,
+        })
+    }
+
+    fn label_index(&self, c: char) -> Option<usize> {
+        let c = c.to_ascii_uppercase();
+        self.labels.iter().position(|&label| label == c)
+    }
+}
+
+impl Cipher for Adfgvx {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let labels: String = plaintext
+            .chars()
+            .filter_map(|c| self.square.coordinates(c))
+            .flat_map(|(row, col)| [self.labels[row], self.labels[col]])
+            .collect();
+        if labels.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(
+                "Plaintext must contain at least one alphanumeric character".to_string(),
+            ));
+        }
+        self.transposition.encrypt(&labels)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        if ciphertext
+            .chars()
+            .any(|c| c.is_alphanumeric() && self.label_index(c).is_none())
+        {
+            return Err(PolygraphiaError::DecryptionError(format!(
+                "Ciphertext may only contain the letters {}",
+                self.labels.iter().collect::<String>()
+            )));
+        }
+        let labels: String = ciphertext
+            .chars()
+            .filter(|&c| self.label_index(c).is_some())
+            .collect();
+        if labels.is_empty() || labels.len() % 2 == 1 {
+            return Err(PolygraphiaError::DecryptionError(
+                "Ciphertext must contain an even, non-zero number of letters".to_string(),
+            ));
+        }
+        let fractionated: Vec<usize> = self
+            .transposition
+            .decrypt(&labels)?
+            .chars()
+            .filter_map(|c| self.label_index(c))
+            .collect();
+        Ok(fractionated
+            .chunks(2)
+            .map(|pair| self.square.symbol_at(pair[0], pair[1]))
+            .collect())
+    }
+
+    fn name(&self) -> &str {
+        if self.labels.len() == ADFGX_LABELS.len() {
+            "adfgx"
+        } else {
+            "adfgvx"
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adfgx_known_answer() {
+        let cipher = Adfgvx::adfgx("btalpdhozkqfvsngicuxmrewy", "cargo").unwrap();
+        assert_eq!(
+            cipher.encrypt("Attack at once").unwrap(),
+            "FAXDFADDDGDGFFFAFAXAFAFX"
+        );
+        assert_eq!(
+            cipher.decrypt("FAXDF ADDDG DGFFF AFAXA FAFX").unwrap(),
+            "attackatonce"
+        );
+        assert_eq!(cipher.name(), "adfgx");
+    }
+
+    #[test]
+    fn test_adfgvx_known_answer() {
+        let cipher = Adfgvx::new("na1c3h8tb2ome5wrpd4f6g7i9j0klqsuvxyz", "privacy").unwrap();
+        assert_eq!(
+            cipher.encrypt("Attack at 1200AM").unwrap(),
+            "DGDDDAGDDGAFADDFDADVDVFAADVX"
+        );
+        assert_eq!(
+            cipher.decrypt("DGDDDAGDDGAFADDFDADVDVFAADVX").unwrap(),
+            "attackat1200am"
+        );
+        assert_eq!(cipher.name(), "adfgvx");
+    }
+
+    #[test]
+    fn test_adfgvx_random() {
+        let cipher = Adfgvx::random_with(8, &mut KeyGenerator::from_seed(2)).unwrap();
+        assert_eq!(cipher.square_key().len(), 36);
+        assert_eq!(cipher.transposition_key().len(), 8);
+        let ciphertext = cipher.encrypt("attack at 1200").unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "attackat1200");
+
+        assert!(Adfgvx::random(6).is_ok());
+        assert!(Adfgvx::random(0).is_err());
+    }
+
+    #[test]
+    fn test_adfgvx_invalid() {
+        assert!(Adfgvx::new("key", "").is_err());
+        let cipher = Adfgvx::new("key", "secret").unwrap();
+        assert!(cipher.encrypt("!!!").is_err());
+        assert!(cipher.decrypt("ADF").is_err());
+        assert!(cipher.decrypt("ADFB").is_err());
+        assert!(cipher.decrypt("").is_err());
+    }
+}
@@ -1,13 +1,18 @@
 use crate::error::PolygraphiaError;
-use crate::traits::Cipher;
+use crate::stream::BlockState;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::Alphabet;
+use crate::utils::KeyGenerator;
 use crate::utils::math;
 use crate::utils::mode::TextMode;
+use crate::utils::text;
 
 #[derive(Debug, Clone)]
 pub struct Affine {
     shift: u8,
     multiplier: u8,
     inv_multiplier: u8,
+    alphabet: Alphabet,
     mode: TextMode,
 }
 
@@ -16,6 +21,27 @@ impl Affine {
         Self::with_mode(shift, multiplier, TextMode::default())
     }
 
+    pub fn with_alphabet(
+        shift: u8,
+        multiplier: u8,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        Self::build(shift, multiplier, alphabet, TextMode::default())
+    }
+
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        Self::random_with(&mut KeyGenerator::new(), Alphabet::default())
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let shift = generator.shift(&alphabet);
+        let multiplier = generator.multiplier(&alphabet);
+        Self::with_alphabet(shift, multiplier, alphabet)
+    }
+
     pub fn shift(&self) -> u8 {
         self.shift
     }
@@ -24,18 +50,24 @@ impl Affine {
         self.multiplier
     }
 
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
     pub fn mode(&self) -> TextMode {
         self.mode
     }
 
     pub fn set_shift(&mut self, shift: u8) -> Result<(), PolygraphiaError> {
-        self.shift = shift % 26;
+        self.shift = (shift as usize % self.alphabet.len()) as u8;
         Ok(())
     }
 
     pub fn set_multiplier(&mut self, multiplier: u8) -> Result<(), PolygraphiaError> {
-        Self::validate_multiplier(multiplier)?;
+        let modulus = self.modulus();
+        Self::validate_multiplier(multiplier, modulus)?;
         self.multiplier = multiplier;
+        self.inv_multiplier = math::mod_inverse(multiplier as u64, modulus as u64)? as u8;
         Ok(())
     }
 
@@ -44,94 +76,134 @@ impl Affine {
     }
 
     fn with_mode(shift: u8, multiplier: u8, mode: TextMode) -> Result<Self, PolygraphiaError> {
-        Self::validate_multiplier(multiplier)?;
-        let inv_multiplier = math::mod_inverse(multiplier, 26)?;
+        Self::build(shift, multiplier, Alphabet::default(), mode)
+    }
+
+    fn build(
+        shift: u8,
+        multiplier: u8,
+        alphabet: Alphabet,
+        mode: TextMode,
+    ) -> Result<Self, PolygraphiaError> {
+        let modulus = alphabet.len() as u8;
+        Self::validate_multiplier(multiplier, modulus)?;
+        let inv_multiplier = math::mod_inverse(multiplier as u64, modulus as u64)? as u8;
         Ok(Affine {
-            shift: shift % 26,
+            shift: shift % modulus,
             multiplier,
             inv_multiplier,
+            alphabet,
             mode,
         })
     }
 
-    fn validate_multiplier(multiplier: u8) -> Result<(), PolygraphiaError> {
-        if !math::are_coprime(multiplier, 26) {
+    fn modulus(&self) -> u8 {
+        self.alphabet.len() as u8
+    }
+
+    fn validate_multiplier(multiplier: u8, modulus: u8) -> Result<(), PolygraphiaError> {
+        if !math::are_coprime(multiplier as u64, modulus as u64) {
+            let valid: Vec<String> = (1..modulus)
+                .filter(|&m| math::are_coprime(m as u64, modulus as u64))
+                .map(|m| m.to_string())
+                .collect();
             return Err(PolygraphiaError::InvalidKey(format!(
-                "Multiplier {} must be coprime with 26 (gcd = {}). Valid values: 1, 3, 5, 7, 9, 11, 15, 17, 19, 21, 23, 25",
+                "Multiplier {} must be coprime with {} (gcd = {}). Valid values: {}",
                 multiplier,
-                math::gcd(multiplier, 26)
+                modulus,
+                math::gcd(multiplier as u64, modulus as u64),
+                valid.join(", ")
             )));
         }
         Ok(())
     }
 
     fn process_char(&self, c: char, encrypt: bool) -> char {
-        if !c.is_ascii_alphabetic() {
+        let Some(idx) = self.alphabet.index_of(c) else {
             return c;
-        }
-        let is_uppercase = c.is_ascii_uppercase();
-        let base = if is_uppercase { b'A' } else { b'a' };
-        let c_lower = c.to_ascii_lowercase();
-        let idx = (c_lower as u8) - b'a';
+        };
+        let modulus = self.modulus() as u16;
+        let idx = idx as u16;
         let processed_idx = if encrypt {
-            ((self.multiplier as u16 * idx as u16 + self.shift as u16) % 26) as u8
+            (self.multiplier as u16 * idx + self.shift as u16) % modulus
         } else {
-            let shifted = (idx as i16 - self.shift as i16).rem_euclid(26) as u8;
-            ((self.inv_multiplier as u16 * shifted as u16) % 26) as u8
+            let shifted = (idx + modulus - self.shift as u16) % modulus;
+            (self.inv_multiplier as u16 * shifted) % modulus
         };
-        (base + processed_idx) as char
-    }
-
-    fn process_text(&self, text: &str, encrypt: bool) -> String {
-        match self.mode {
-            TextMode::AlphaOnly => text
-                .chars()
-                .filter(|c| c.is_ascii_alphabetic())
-                .map(|c| self.process_char(c, encrypt))
-                .collect(),
-            TextMode::PreserveAll => text
-                .chars()
-                .map(|c| {
-                    if c.is_ascii_alphabetic() {
-                        self.process_char(c, encrypt)
-                    } else {
-                        c
-                    }
-                })
-                .collect(),
+        self.alphabet.symbol_like(processed_idx as usize, c)
+    }
+
+    /// The character `c` becomes, or `None` if it is dropped.
+    fn map_char(&self, c: char, encrypt: bool) -> Option<char> {
+        if self.alphabet.contains(c) {
+            Some(self.process_char(c, encrypt))
+        } else if self.mode == TextMode::PreserveAll {
+            Some(c)
+        } else {
+            None
         }
     }
+
+    fn process_text(&self, text: &str, encrypt: bool, out: &mut String) {
+        out.extend(text.chars().filter_map(|c| self.map_char(c, encrypt)));
+    }
 }
 
 impl Cipher for Affine {
     fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(plaintext.len());
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
         if plaintext.is_empty() {
             return Err(PolygraphiaError::InvalidInput(
                 "Empty plaintext".to_string(),
             ));
         }
-        let result = self.process_text(plaintext, true);
-        if self.mode == TextMode::AlphaOnly && result.is_empty() {
+        let start = out.len();
+        self.process_text(plaintext, true, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
             return Err(PolygraphiaError::InvalidInput(
                 "Plaintext must contain at least one alphabetic character".to_string(),
             ));
         }
-        Ok(result)
+        Ok(())
     }
 
-    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
         if ciphertext.is_empty() {
             return Err(PolygraphiaError::InvalidInput(
                 "Empty ciphertext".to_string(),
             ));
         }
-        let result = self.process_text(ciphertext, false);
-        if self.mode == TextMode::AlphaOnly && result.is_empty() {
+        let start = out.len();
+        self.process_text(ciphertext, false, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
             return Err(PolygraphiaError::InvalidInput(
                 "ciphertext must have at least one alphabetic character".to_string(),
             ));
         }
-        Ok(result)
+        Ok(())
+    }
+
+    fn encrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Plaintext", || {
+            |c| self.map_char(c, true)
+        })
+    }
+
+    fn decrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Ciphertext", || {
+            |c| self.map_char(c, false)
+        })
     }
 
     fn name(&self) -> &str {
@@ -139,6 +211,50 @@ impl Cipher for Affine {
     }
 }
 
+impl StreamCipher for Affine {
+    type State = BlockState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], true, out);
+        Ok(cut)
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], false, out);
+        Ok(cut)
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.encrypt_into(text, out)
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.decrypt_into(text, out)
+    }
+}
+
 impl Drop for Affine {
     fn drop(&mut self) {
         self.shift = 0;
@@ -318,6 +434,56 @@ mod tests {
         assert_eq!(cipher.encrypt("hello").unwrap(), "rclla");
     }
 
+    #[test]
+    fn test_affine_custom_alphabet() {
+        // 29 is prime, so every non-zero multiplier is valid
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz .,").unwrap();
+        let cipher = Affine::with_alphabet(8, 13, alphabet).unwrap();
+        assert_eq!(cipher.alphabet().len(), 29);
+
+        let plaintext = "Hello, World. 42";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert!(encrypted.ends_with("42"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_affine_custom_alphabet_multiplier_validation() {
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456789").unwrap();
+
+        // 36 = 2^2 * 3^2
+        assert!(Affine::with_alphabet(0, 3, alphabet.clone()).is_err());
+        assert!(Affine::with_alphabet(0, 5, alphabet.clone()).is_ok());
+        let err = Affine::with_alphabet(0, 2, alphabet).unwrap_err();
+        assert!(err.to_string().contains("coprime with 36"));
+    }
+
+    #[test]
+    fn test_affine_arabic() {
+        // 28 = 2^2 * 7
+        assert!(Affine::with_alphabet(3, 14, Alphabet::arabic()).is_err());
+
+        let cipher = Affine::with_alphabet(3, 5, Alphabet::arabic()).unwrap();
+        let plaintext = "مرحبا بالعالم!";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_ne!(encrypted, plaintext);
+        assert!(encrypted.ends_with('!'));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_affine_random() {
+        let mut generator = KeyGenerator::from_seed(11);
+        for alphabet in [Alphabet::english(), Alphabet::arabic()] {
+            for _ in 0..20 {
+                let cipher = Affine::random_with(&mut generator, alphabet.clone()).unwrap();
+                let multiplier = cipher.multiplier() as u64;
+                assert!(math::are_coprime(multiplier, alphabet.len() as u64));
+            }
+        }
+        assert!(Affine::random().is_ok());
+    }
+
     #[test]
     fn test_affine_modulo_behavior() {
         // Test that shift wraps correctly
@@ -0,0 +1,237 @@
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::text;
+use crate::utils::{Alphabet, KeyGenerator, PolybiusSquare, TextMode};
+
+/// Delastelle's Bifid cipher: the row and column numbers of each letter in a
+/// Polybius square are written in two lines and read back across, in blocks
+/// of `period` letters.
+#[derive(Debug, Clone)]
+pub struct Bifid {
+    square: PolybiusSquare,
+    period: Option<usize>,
+    mode: TextMode,
+}
+
+impl Bifid {
+    /// Square from `key` over the 25-letter alphabet with `j` merged into `i`,
+    /// fractionating the whole message at once.
+    pub fn new(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(key, None, Alphabet::english_merged_ij())
+    }
+
+    pub fn with_period(key: &str, period: usize) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(key, Some(period), Alphabet::english_merged_ij())
+    }
+
+    /// `alphabet` must fill a square grid, e.g. 36 symbols for 6x6.
+    pub fn with_alphabet(
+        key: &str,
+        period: Option<usize>,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let side = alphabet.len().isqrt();
+        if side * side != alphabet.len() {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Bifid needs a square grid, but {} symbols do not make one",
+                alphabet.len()
+            )));
+        }
+        Self::validate_period(period)?;
+        Ok(Bifid {
+            square: PolybiusSquare::with_shape(key, alphabet, side, side)?,
+            period,
+            mode: TextMode::default(),
+        })
+    }
+
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        Self::random_with(
+            &mut KeyGenerator::new(),
+            None,
+            Alphabet::english_merged_ij(),
+        )
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        period: Option<usize>,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let key = generator.permutation(&alphabet);
+        Self::with_alphabet(&key, period, alphabet)
+    }
+
+    pub fn key(&self) -> &str {
+        self.square.key()
+    }
+
+    pub fn square(&self) -> &PolybiusSquare {
+        &self.square
+    }
+
+    /// Letters per block, or `None` for the whole message.
+    pub fn period(&self) -> Option<usize> {
+        self.period
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    pub fn set_period(&mut self, period: Option<usize>) -> Result<(), PolygraphiaError> {
+        Self::validate_period(period)?;
+        self.period = period;
+        Ok(())
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    fn validate_period(period: Option<usize>) -> Result<(), PolygraphiaError> {
+        if period == Some(0) {
+            return Err(PolygraphiaError::InvalidKey(
+                "Period must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn process_block(&self, block: &[char], encrypt: bool) -> Vec<char> {
+        let coordinates: Vec<(usize, usize)> = block
+            .iter()
+            .filter_map(|&c| self.square.coordinates(c))
+            .collect();
+        let len = coordinates.len();
+        let output: Vec<(usize, usize)> = if encrypt {
+            let line: Vec<usize> = coordinates
+                .iter()
+                .map(|&(row, _)| row)
+                .chain(coordinates.iter().map(|&(_, col)| col))
+                .collect();
+            line.chunks(2).map(|pair| (pair[0], pair[1])).collect()
+        } else {
+            let line: Vec<usize> = coordinates
+                .iter()
+                .flat_map(|&(row, col)| [row, col])
+                .collect();
+            (0..len).map(|i| (line[i], line[len + i])).collect()
+        };
+        output
+            .into_iter()
+            .zip(block)
+            .map(|((row, col), &original)| {
+                let idx = self.square.index_at(row, col);
+                self.square.alphabet().symbol_like(idx, original)
+            })
+            .collect()
+    }
+
+    fn process_text(&self, text: &str, encrypt: bool) -> String {
+        let alphabet = self.square.alphabet();
+        let letters: Vec<char> = text.chars().filter(|&c| alphabet.contains(c)).collect();
+        let period = self.period.unwrap_or(letters.len()).max(1);
+        let letters: Vec<char> = letters
+            .chunks(period)
+            .flat_map(|block| self.process_block(block, encrypt))
+            .collect();
+        match self.mode {
+            TextMode::PreserveAll => text::restore_layout(text, &letters, alphabet),
+            TextMode::AlphaOnly => letters.into_iter().collect(),
+        }
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
+        }
+        if !text.chars().any(|c| self.square.alphabet().contains(c)) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one alphabetic character"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for Bifid {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        Ok(self.process_text(plaintext, true))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        Ok(self.process_text(ciphertext, false))
+    }
+
+    fn name(&self) -> &str {
+        "bifid"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bifid_known_answer() {
+        let cipher = Bifid::new("bgwkzqpndsioaxefclumthyvr").unwrap();
+        assert_eq!(cipher.encrypt("FLEEATONCE").unwrap(), "UAEOLWRINS");
+        assert_eq!(cipher.decrypt("UAEOLWRINS").unwrap(), "FLEEATONCE");
+    }
+
+    #[test]
+    fn test_bifid_period() {
+        let cipher = Bifid::with_period("bgwkzqpndsioaxefclumthyvr", 5).unwrap();
+        let whole = Bifid::new("bgwkzqpndsioaxefclumthyvr").unwrap();
+
+        // Each block of five letters is fractionated on its own
+        let blocks = whole.encrypt("fleea").unwrap() + &whole.encrypt("tonce").unwrap();
+        assert_eq!(cipher.encrypt("fleeatonce").unwrap(), blocks);
+        assert_eq!(cipher.decrypt(&blocks).unwrap(), "fleeatonce");
+        assert!(Bifid::with_period("key", 0).is_err());
+    }
+
+    #[test]
+    fn test_bifid_random() {
+        let mut generator = KeyGenerator::from_seed(3);
+        let cipher =
+            Bifid::random_with(&mut generator, Some(5), Alphabet::english_merged_ij()).unwrap();
+        assert_eq!(cipher.key().len(), 25);
+        let ciphertext = cipher.encrypt("Flee at once").unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Flee at once");
+
+        assert!(Bifid::random().is_ok());
+        assert!(Bifid::random_with(&mut generator, None, Alphabet::english()).is_err());
+    }
+
+    #[test]
+    fn test_bifid_modes() {
+        let mut cipher = Bifid::new("playfair").unwrap();
+        let ciphertext = cipher.encrypt("Hello, World!").unwrap();
+        assert_eq!(ciphertext.len(), "Hello, World!".len());
+        assert!(ciphertext.starts_with(char::is_uppercase));
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Hello, World!");
+
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert_eq!(cipher.encrypt("Hello, World!").unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_bifid_six_by_six() {
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456789").unwrap();
+        let cipher = Bifid::with_alphabet("secret 2024", Some(7), alphabet).unwrap();
+        assert_eq!(cipher.square().rows(), 6);
+        let ciphertext = cipher.encrypt("Meet at 10am, Jetty 7").unwrap();
+        assert_eq!(
+            cipher.decrypt(&ciphertext).unwrap(),
+            "Meet at 10am, Jetty 7"
+        );
+
+        assert!(Bifid::with_alphabet("key", None, Alphabet::english()).is_err());
+    }
+}
@@ -1,10 +1,12 @@
 use crate::error::PolygraphiaError;
-use crate::traits::Cipher;
-use crate::utils::TextMode;
+use crate::stream::BlockState;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::{Alphabet, KeyGenerator, TextMode, text};
 
 #[derive(Debug, Clone)]
 pub struct Caesar {
     shift: u8,
+    alphabet: Alphabet,
     mode: TextMode,
 }
 
@@ -13,16 +15,35 @@ impl Caesar {
         Self::with_mode(shift, TextMode::default())
     }
 
+    pub fn with_alphabet(shift: u8, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        Self::build(shift, alphabet, TextMode::default())
+    }
+
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        Self::random_with(&mut KeyGenerator::new(), Alphabet::default())
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(generator.shift(&alphabet), alphabet)
+    }
+
     pub fn shift(&self) -> u8 {
         self.shift
     }
 
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
     pub fn mode(&self) -> TextMode {
         self.mode
     }
 
     pub fn set_shift(&mut self, shift: u8) -> Result<(), PolygraphiaError> {
-        self.shift = shift % 26;
+        self.shift = (shift as usize % self.alphabet.len()) as u8;
         Ok(())
     }
 
@@ -31,79 +52,101 @@ impl Caesar {
     }
 
     fn with_mode(shift: u8, mode: TextMode) -> Result<Self, PolygraphiaError> {
+        Self::build(shift, Alphabet::default(), mode)
+    }
+
+    fn build(shift: u8, alphabet: Alphabet, mode: TextMode) -> Result<Self, PolygraphiaError> {
         Ok(Caesar {
-            shift: shift % 26,
+            shift: (shift as usize % alphabet.len()) as u8,
+            alphabet,
             mode,
         })
     }
 
     fn shift_char(&self, c: char, encrypt: bool) -> char {
-        if !c.is_ascii_alphabetic() {
+        let Some(idx) = self.alphabet.index_of(c) else {
             return c;
-        }
-        let is_uppercase = c.is_ascii_uppercase();
-        let base = if is_uppercase { b'A' } else { b'a' };
-        let c_lower = c.to_ascii_lowercase();
-        let idx = (c_lower as u8) - b'a';
+        };
+        let len = self.alphabet.len();
         let shift = if encrypt {
-            self.shift as i8
+            self.shift as usize
         } else {
-            -(self.shift as i8)
+            len - self.shift as usize
         };
-        let shifted = (idx as i8 + shift).rem_euclid(26) as u8;
-        (base + shifted) as char
-    }
-
-    fn process_text(&self, text: &str, encrypt: bool) -> String {
-        match self.mode {
-            TextMode::AlphaOnly => text
-                .chars()
-                .filter(|c| c.is_ascii_alphabetic())
-                .map(|c| self.shift_char(c, encrypt))
-                .collect(),
-            TextMode::PreserveAll => text
-                .chars()
-                .map(|c| {
-                    if c.is_ascii_alphabetic() {
-                        self.shift_char(c, encrypt)
-                    } else {
-                        c
-                    }
-                })
-                .collect(),
+        self.alphabet.symbol_like((idx + shift) % len, c)
+    }
+
+    /// The character `c` becomes, or `None` if it is dropped.
+    fn map_char(&self, c: char, encrypt: bool) -> Option<char> {
+        if self.alphabet.contains(c) {
+            Some(self.shift_char(c, encrypt))
+        } else if self.mode == TextMode::PreserveAll {
+            Some(c)
+        } else {
+            None
         }
     }
+
+    fn process_text(&self, text: &str, encrypt: bool, out: &mut String) {
+        out.extend(text.chars().filter_map(|c| self.map_char(c, encrypt)));
+    }
 }
 
 impl Cipher for Caesar {
     fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(plaintext.len());
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
         if plaintext.is_empty() {
             return Err(PolygraphiaError::InvalidInput(
                 "Empty plaintext".to_string(),
             ));
         }
-        let result = self.process_text(plaintext, true);
-        if self.mode == TextMode::AlphaOnly && result.is_empty() {
+        let start = out.len();
+        self.process_text(plaintext, true, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
             return Err(PolygraphiaError::InvalidInput(
                 "Plaintext must contain at least one alphabetic character".to_string(),
             ));
         }
-        Ok(result)
+        Ok(())
     }
 
-    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
         if ciphertext.is_empty() {
             return Err(PolygraphiaError::InvalidInput(
                 "Empty ciphertext".to_string(),
             ));
         }
-        let result = self.process_text(ciphertext, false);
-        if self.mode == TextMode::AlphaOnly && result.is_empty() {
+        let start = out.len();
+        self.process_text(ciphertext, false, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
             return Err(PolygraphiaError::InvalidInput(
                 "ciphertext must have at least one alphabetic character".to_string(),
             ));
         }
-        Ok(result)
+        Ok(())
+    }
+
+    fn encrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Plaintext", || {
+            |c| self.map_char(c, true)
+        })
+    }
+
+    fn decrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Ciphertext", || {
+            |c| self.map_char(c, false)
+        })
     }
 
     fn name(&self) -> &str {
@@ -111,6 +154,50 @@ impl Cipher for Caesar {
     }
 }
 
+impl StreamCipher for Caesar {
+    type State = BlockState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], true, out);
+        Ok(cut)
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], false, out);
+        Ok(cut)
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.encrypt_into(text, out)
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.decrypt_into(text, out)
+    }
+}
+
 impl Drop for Caesar {
     fn drop(&mut self) {
         self.shift = 0;
@@ -272,6 +359,43 @@ mod tests {
         assert_eq!(cipher.encrypt("hello").unwrap(), "khoor");
     }
 
+    #[test]
+    fn test_caesar_custom_alphabet() {
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456789").unwrap();
+        let cipher = Caesar::with_alphabet(5, alphabet).unwrap();
+        assert_eq!(cipher.alphabet().len(), 36);
+
+        // Digits are part of the alphabet now
+        assert_eq!(cipher.encrypt("xyz 789!").unwrap(), "234 cde!");
+        assert_eq!(cipher.decrypt("234 cde!").unwrap(), "xyz 789!");
+        assert_eq!(cipher.encrypt("Hello").unwrap(), "Mjqqt");
+
+        // Shifts wrap at the alphabet size
+        let cipher = Caesar::with_alphabet(37, Alphabet::new("abc").unwrap()).unwrap();
+        assert_eq!(cipher.shift(), 1);
+    }
+
+    #[test]
+    fn test_caesar_arabic() {
+        let cipher = Caesar::with_alphabet(3, Alphabet::arabic()).unwrap();
+        assert_eq!(cipher.encrypt("سلام عليكم").unwrap(), "ضهثو قهتنو");
+        assert_eq!(cipher.decrypt("ضهثو قهتنو").unwrap(), "سلام عليكم");
+
+        // Alef with hamza is read as a plain alef
+        assert_eq!(cipher.encrypt("أ").unwrap(), "ث");
+    }
+
+    #[test]
+    fn test_caesar_random() {
+        let cipher = Caesar::random().unwrap();
+        assert_ne!(cipher.shift(), 0);
+
+        let a = Caesar::random_with(&mut KeyGenerator::from_seed(3), Alphabet::english()).unwrap();
+        let b = Caesar::random_with(&mut KeyGenerator::from_seed(3), Alphabet::english()).unwrap();
+        assert_eq!(a.shift(), b.shift());
+        assert_eq!(a.decrypt(&a.encrypt("Hello").unwrap()).unwrap(), "Hello");
+    }
+
     #[test]
     fn test_numbers_and_symbols() {
         let cipher = Caesar::new(5).unwrap();
@@ -289,4 +413,34 @@ mod tests {
         // Test mixed content
         assert_eq!(cipher.encrypt("Password123!").unwrap(), "Ufxxbtwi123!");
     }
+
+    #[test]
+    fn test_caesar_into_and_in_place() {
+        let mut cipher = Caesar::new(3).unwrap();
+        let mut out = String::from("> ");
+        cipher.encrypt_into("Hello", &mut out).unwrap();
+        cipher.encrypt_into(", World!", &mut out).unwrap();
+        assert_eq!(out, "> Khoor, Zruog!");
+
+        let mut data = b"Attack at dawn".to_vec();
+        let (ptr, len, capacity) = (data.as_ptr(), data.len(), data.capacity());
+        cipher.encrypt_bytes_in_place(&mut data).unwrap();
+        assert_eq!(data, b"Dwwdfn dw gdzq");
+        assert_eq!(
+            (data.as_ptr(), data.len(), data.capacity()),
+            (ptr, len, capacity)
+        );
+        cipher.decrypt_bytes_in_place(&mut data).unwrap();
+        assert_eq!(data, b"Attack at dawn");
+
+        let mut data = "Æsop's fables".as_bytes().to_vec();
+        cipher.encrypt_bytes_in_place(&mut data).unwrap();
+        assert_eq!(data, "Ævrs'v ideohv".as_bytes());
+
+        let mut data = b"Attack at dawn".to_vec();
+        // Dropping the spaces would change the length
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert!(cipher.encrypt_bytes_in_place(&mut data).is_err());
+        assert_eq!(data, b"Attack at dawn");
+    }
 }
@@ -0,0 +1,479 @@
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::math;
+use crate::utils::padding::{self, Padding, Unpadding};
+use crate::utils::text;
+use crate::utils::{Alphabet, KeyGenerator, TextMode};
+
+/// Longest block `ColumnFill::Complete` may pad to, so a pair of wide keys
+/// cannot bury a short message under thousands of fillers.
+const MAX_COMPLETE_BLOCK: usize = 1024;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnFill {
+    /// The last row is left short, so some columns are one letter shorter.
+    #[default]
+    Incomplete,
+    /// The last row is padded so every column has the same length.
+    Complete,
+}
+
+/// Writes the letters in rows under a keyword and reads the columns off in the
+/// alphabetical order of the keyword's letters. With a second keyword the
+/// transposition is repeated, giving double transposition.
+#[derive(Debug, Clone)]
+pub struct ColumnarTransposition {
+    keys: Vec<String>,
+    orders: Vec<Vec<usize>>,
+    alphabet: Alphabet,
+    mode: TextMode,
+    fill: ColumnFill,
+    filler: char,
+    padding: Padding,
+    unpadding: Unpadding,
+}
+
+impl ColumnarTransposition {
+    pub fn new(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_keys(&[key], Alphabet::default())
+    }
+
+    pub fn double(first: &str, second: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_keys(&[first, second], Alphabet::default())
+    }
+
+    /// One transposition per key, applied in order when encrypting; one or
+    /// two keys are accepted.
+    pub fn with_keys(keys: &[&str], alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        if !(1..=2).contains(&keys.len()) {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Expected one or two keys, got {}",
+                keys.len()
+            )));
+        }
+        let keys = keys
+            .iter()
+            .map(|key| Self::prepare_key(key, &alphabet))
+            .collect::<Result<Vec<_>, _>>()?;
+        let orders = keys
+            .iter()
+            .map(|key| Self::column_order(key, &alphabet))
+            .collect();
+        Ok(ColumnarTransposition {
+            keys,
+            orders,
+            filler: padding::default_filler(&alphabet),
+            alphabet,
+            mode: TextMode::default(),
+            fill: ColumnFill::default(),
+            padding: Padding::default(),
+            unpadding: Unpadding::default(),
+        })
+    }
+
+    /// Builds the cipher from numeric keys, one per transposition. Each lists
+    /// the reading rank of every column, e.g. `[2, 0, 1]` reads the second
+    /// column first and the first column last.
+    pub fn from_orders(
+        orders: &[Vec<usize>],
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let keys = orders
+            .iter()
+            .map(|order| {
+                let mut sorted = order.clone();
+                sorted.sort_unstable();
+                if sorted.is_empty() || sorted.iter().enumerate().any(|(i, &rank)| i != rank) {
+                    return Err(PolygraphiaError::InvalidKey(format!(
+                        "Column order {order:?} is not a permutation of 0..{}",
+                        order.len()
+                    )));
+                }
+                if order.len() > alphabet.len() {
+                    return Err(PolygraphiaError::InvalidKey(format!(
+                        "Column order {order:?} is longer than the alphabet"
+                    )));
+                }
+                Ok(order.iter().map(|&rank| alphabet.symbol(rank)).collect())
+            })
+            .collect::<Result<Vec<String>, _>>()?;
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        Self::with_keys(&keys, alphabet)
+    }
+
+    pub fn random(widths: &[usize]) -> Result<Self, PolygraphiaError> {
+        Self::random_with(widths, &mut KeyGenerator::new(), Alphabet::default())
+    }
+
+    /// One key of each width, drawn without repeated letters so every column
+    /// order is equally likely.
+    pub fn random_with(
+        widths: &[usize],
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let keys = widths
+            .iter()
+            .map(|&width| {
+                if width == 0 || width > alphabet.len() {
+                    return Err(PolygraphiaError::InvalidKey(format!(
+                        "Cannot draw a key of width {width} from {} symbols",
+                        alphabet.len()
+                    )));
+                }
+                Ok(generator
+                    .permutation(&alphabet)
+                    .chars()
+                    .take(width)
+                    .collect())
+            })
+            .collect::<Result<Vec<String>, _>>()?;
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        Self::with_keys(&keys, alphabet)
+    }
+
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// Reading rank of every column, one list per transposition.
+    pub fn column_orders(&self) -> &[Vec<usize>] {
+        &self.orders
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    pub fn fill(&self) -> ColumnFill {
+        self.fill
+    }
+
+    pub fn filler(&self) -> char {
+        self.filler
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.padding
+    }
+
+    pub fn unpadding(&self) -> Unpadding {
+        self.unpadding
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    /// Fails when the keys' widths fill blocks longer than 1024 letters, or a
+    /// length marker could not record the padding of a block.
+    pub fn set_fill(&mut self, fill: ColumnFill) -> Result<(), PolygraphiaError> {
+        self.validate_padding(fill, self.padding)?;
+        self.fill = fill;
+        Ok(())
+    }
+
+    pub fn set_filler(&mut self, filler: char) -> Result<(), PolygraphiaError> {
+        self.filler = padding::validate_filler(filler, &self.alphabet)?;
+        Ok(())
+    }
+
+    /// `Padding::LengthMarker` under `ColumnFill::Complete` needs the block
+    /// that fills every rectangle, the lcm of the key widths, to be shorter
+    /// than the alphabet.
+    pub fn set_padding(&mut self, padding: Padding) -> Result<(), PolygraphiaError> {
+        self.validate_padding(self.fill, padding)?;
+        self.padding = padding;
+        Ok(())
+    }
+
+    pub fn set_unpadding(&mut self, unpadding: Unpadding) {
+        self.unpadding = unpadding;
+    }
+
+    fn prepare_key(key: &str, alphabet: &Alphabet) -> Result<String, PolygraphiaError> {
+        let prepared: String = key.chars().filter_map(|c| alphabet.normalize(c)).collect();
+        if prepared.is_empty() {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Key '{key}' contains no letters of the alphabet"
+            )));
+        }
+        Ok(prepared)
+    }
+
+    /// Rank of each key letter by alphabet position; repeated letters rank left to right.
+    fn column_order(key: &str, alphabet: &Alphabet) -> Vec<usize> {
+        let letters: Vec<usize> = key.chars().filter_map(|c| alphabet.index_of(c)).collect();
+        let mut columns: Vec<usize> = (0..letters.len()).collect();
+        columns.sort_by_key(|&col| letters[col]);
+        let mut order = vec![0; letters.len()];
+        for (rank, col) in columns.into_iter().enumerate() {
+            order[col] = rank;
+        }
+        order
+    }
+
+    /// Source position of each ciphertext letter for one transposition.
+    fn reading_order(order: &[usize], length: usize) -> Vec<usize> {
+        let width = order.len();
+        let mut columns: Vec<usize> = (0..width).collect();
+        columns.sort_by_key(|&col| order[col]);
+        columns
+            .into_iter()
+            .flat_map(|col| (col..length).step_by(width))
+            .collect()
+    }
+
+    /// Block size that keeps every rectangle full under `ColumnFill::Complete`.
+    fn block_size(&self) -> Result<usize, PolygraphiaError> {
+        let block = self.orders.iter().try_fold(1usize, |block, order| {
+            (block / math::gcd(block as u64, order.len() as u64) as usize)
+                .checked_mul(order.len())
+                .filter(|&block| block <= MAX_COMPLETE_BLOCK)
+        });
+        block.ok_or_else(|| {
+            PolygraphiaError::InvalidKey(format!(
+                "Keys of widths {:?} fill blocks of more than {MAX_COMPLETE_BLOCK} letters",
+                self.orders.iter().map(Vec::len).collect::<Vec<_>>()
+            ))
+        })
+    }
+
+    fn validate_padding(&self, fill: ColumnFill, padding: Padding) -> Result<(), PolygraphiaError> {
+        if fill == ColumnFill::Incomplete {
+            return Ok(());
+        }
+        let block = self.block_size()?;
+        if padding == Padding::LengthMarker && block >= self.alphabet.len() {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Keys filling blocks of {block} letters are too wide for a length marker"
+            )));
+        }
+        Ok(())
+    }
+
+    fn process_text(&self, text: &str, encrypt: bool) -> Result<String, PolygraphiaError> {
+        let mut letters: Vec<char> = text
+            .chars()
+            .filter(|&c| self.alphabet.contains(c))
+            .collect();
+        let complete = self.fill == ColumnFill::Complete;
+        if encrypt {
+            if complete {
+                padding::pad(
+                    &mut letters,
+                    self.block_size()?,
+                    self.filler,
+                    self.padding,
+                    &self.alphabet,
+                )?;
+            }
+            for order in &self.orders {
+                letters =
+                    text::transpose(&letters, &Self::reading_order(order, letters.len()), true);
+            }
+        } else {
+            for order in self.orders.iter().rev() {
+                letters =
+                    text::transpose(&letters, &Self::reading_order(order, letters.len()), false);
+            }
+            if complete {
+                padding::unpad(
+                    &mut letters,
+                    self.block_size()?,
+                    self.filler,
+                    self.unpadding,
+                    &self.alphabet,
+                )?;
+            }
+        }
+        Ok(match self.mode {
+            TextMode::PreserveAll => text::restore_layout(text, &letters, &self.alphabet),
+            TextMode::AlphaOnly => letters.into_iter().collect(),
+        })
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
+        }
+        if !text.chars().any(|c| self.alphabet.contains(c)) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one alphabetic character"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for ColumnarTransposition {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        self.process_text(plaintext, true)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        self.process_text(ciphertext, false)
+    }
+
+    fn name(&self) -> &str {
+        if self.orders.len() == 2 {
+            "double-columnar"
+        } else {
+            "columnar"
+        }
+    }
+}
+
+impl Drop for ColumnarTransposition {
+    fn drop(&mut self) {
+        for order in &mut self.orders {
+            order.fill(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "WEAREDISCOVEREDFLEEATONCE";
+
+    #[test]
+    fn test_columnar_incomplete() {
+        let cipher = ColumnarTransposition::new("ZEBRAS").unwrap();
+        assert_eq!(cipher.column_orders()[0], [5, 2, 1, 3, 0, 4]);
+        assert_eq!(
+            cipher.encrypt(PLAINTEXT).unwrap(),
+            "EVLNACDTESEAROFODEECWIREE"
+        );
+        assert_eq!(
+            cipher.decrypt("EVLNACDTESEAROFODEECWIREE").unwrap(),
+            PLAINTEXT
+        );
+    }
+
+    #[test]
+    fn test_columnar_complete() {
+        let mut cipher = ColumnarTransposition::new("ZEBRAS").unwrap();
+        cipher.set_fill(ColumnFill::Complete).unwrap();
+        cipher.set_filler('q').unwrap();
+        let ciphertext = cipher.encrypt(PLAINTEXT).unwrap();
+        assert_eq!(ciphertext, "EVLNQACDTQESEAQROFOQDEECQWIREE");
+        assert_eq!(
+            cipher.decrypt(&ciphertext).unwrap(),
+            "WEAREDISCOVEREDFLEEATONCEQQQQQ"
+        );
+
+        cipher.set_unpadding(Unpadding::Heuristic);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn test_columnar_repeated_key_letters() {
+        let cipher = ColumnarTransposition::new("hello").unwrap();
+        assert_eq!(cipher.column_orders()[0], [1, 0, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_double_transposition() {
+        let double = ColumnarTransposition::double("zebras", "striped").unwrap();
+        let mut first = ColumnarTransposition::new("zebras").unwrap();
+        let mut second = ColumnarTransposition::new("striped").unwrap();
+        first.set_mode(TextMode::AlphaOnly);
+        second.set_mode(TextMode::AlphaOnly);
+
+        let ciphertext = double.encrypt(PLAINTEXT).unwrap();
+        assert_eq!(
+            ciphertext,
+            second.encrypt(&first.encrypt(PLAINTEXT).unwrap()).unwrap()
+        );
+        assert_eq!(double.decrypt(&ciphertext).unwrap(), PLAINTEXT);
+        assert_eq!(double.name(), "double-columnar");
+    }
+
+    #[test]
+    fn test_double_transposition_complete() {
+        let mut cipher = ColumnarTransposition::double("abc", "abcd").unwrap();
+        cipher.set_fill(ColumnFill::Complete).unwrap();
+        cipher.set_padding(Padding::LengthMarker).unwrap();
+        cipher.set_unpadding(Unpadding::Exact);
+        let ciphertext = cipher.encrypt("attack at dawn").unwrap();
+        assert_eq!(ciphertext.chars().filter(|c| c.is_alphabetic()).count(), 24);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "attack at dawn");
+
+        // Widths 5 and 6 fill blocks of 30 letters, too many to mark
+        let mut cipher = ColumnarTransposition::double("abcde", "abcdef").unwrap();
+        cipher.set_fill(ColumnFill::Complete).unwrap();
+        assert!(cipher.set_padding(Padding::LengthMarker).is_err());
+        assert_eq!(cipher.padding(), Padding::Filler);
+        cipher.set_fill(ColumnFill::Incomplete).unwrap();
+        cipher.set_padding(Padding::LengthMarker).unwrap();
+        assert!(cipher.set_fill(ColumnFill::Complete).is_err());
+        assert_eq!(cipher.fill(), ColumnFill::Incomplete);
+
+        // Widths 31 and 37 would pad every message to 1147 letters
+        let long = "abcdefghijklmnopqrstuvwxyzabcdefghijk";
+        let mut cipher = ColumnarTransposition::double(&long[..31], long).unwrap();
+        assert!(cipher.set_fill(ColumnFill::Complete).is_err());
+        assert_eq!(cipher.fill(), ColumnFill::Incomplete);
+        assert!(cipher.encrypt("attack at dawn").is_ok());
+    }
+
+    #[test]
+    fn test_columnar_preserves_layout() {
+        let cipher = ColumnarTransposition::new("key").unwrap();
+        let ciphertext = cipher.encrypt("Meet me, at noon!").unwrap();
+        assert_eq!(ciphertext.len(), "Meet me, at noon!".len());
+        assert_eq!(&ciphertext[4..5], " ");
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Meet me, at noon!");
+    }
+
+    #[test]
+    fn test_columnar_from_orders() {
+        let cipher =
+            ColumnarTransposition::from_orders(&[vec![2, 0, 1]], Alphabet::english()).unwrap();
+        assert_eq!(cipher.keys()[0], "cab");
+        assert_eq!(cipher.encrypt("abcdef").unwrap(), "becfad");
+
+        assert!(ColumnarTransposition::from_orders(&[vec![0, 0]], Alphabet::english()).is_err());
+        assert!(ColumnarTransposition::from_orders(&[vec![]], Alphabet::english()).is_err());
+    }
+
+    #[test]
+    fn test_columnar_random() {
+        let mut generator = KeyGenerator::from_seed(9);
+        let cipher =
+            ColumnarTransposition::random_with(&[6, 7], &mut generator, Alphabet::english())
+                .unwrap();
+        assert_eq!(cipher.keys()[0].len(), 6);
+        assert_eq!(cipher.keys()[1].len(), 7);
+        let ciphertext = cipher.encrypt(PLAINTEXT).unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), PLAINTEXT);
+
+        assert!(ColumnarTransposition::random(&[5]).is_ok());
+        assert!(ColumnarTransposition::random(&[0]).is_err());
+        assert!(ColumnarTransposition::random(&[27]).is_err());
+        assert!(ColumnarTransposition::random(&[]).is_err());
+    }
+
+    #[test]
+    fn test_columnar_invalid() {
+        assert!(ColumnarTransposition::new("123").is_err());
+        assert!(ColumnarTransposition::with_keys(&[], Alphabet::english()).is_err());
+        assert!(
+            ColumnarTransposition::with_keys(&["one", "two", "three"], Alphabet::english())
+                .is_err()
+        );
+        let cipher = ColumnarTransposition::new("key").unwrap();
+        assert!(cipher.encrypt("").is_err());
+        assert!(cipher.decrypt("!!").is_err());
+    }
+}
@@ -0,0 +1,729 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::error::PolygraphiaError;
+use crate::stream::BlockState;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::{Alphabet, KeyGenerator, TextMode, text};
+
+/// The wheels issued with the Wehrmacht and Kriegsmarine machines. `Beta` and
+/// `Gamma` are the thin Greek wheels that only fit the fourth slot of the M4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rotor {
+    I,
+    II,
+    III,
+    IV,
+    V,
+    VI,
+    VII,
+    VIII,
+    Beta,
+    Gamma,
+}
+
+impl Rotor {
+    /// The wheels that fit the three stepping slots.
+    pub const STEPPING: [Rotor; 8] = [
+        Rotor::I,
+        Rotor::II,
+        Rotor::III,
+        Rotor::IV,
+        Rotor::V,
+        Rotor::VI,
+        Rotor::VII,
+        Rotor::VIII,
+    ];
+
+    fn wiring(self) -> &'static [u8; 26] {
+        match self {
+            Rotor::I => b"EKMFLGDQVZNTOWYHXUSPAIBRCJ",
+            Rotor::II => b"AJDKSIRUXBLHWTMCQGZNPYFVOE",
+            Rotor::III => b"BDFHJLCPRTXVZNYEIWGAKMUSQO",
+            Rotor::IV => b"ESOVPZJAYQUIRHXLNFTGKDCMWB",
+            Rotor::V => b"VZBRGITYUPSDNHLXAWMJQOFECK",
+            Rotor::VI => b"JPGVOUMFYQBENHZRDKASXLICTW",
+            Rotor::VII => b"NZJHGRCXMYSWBOUFAIVLPEKQDT",
+            Rotor::VIII => b"FKQHTLXOCBJSPDZRAMEWNIYUGV",
+            Rotor::Beta => b"LEYJVCNIXWPBQMDRTAKZGFUHOS",
+            Rotor::Gamma => b"FSOKANUERHMBTIYCWLQPZXVGJD",
+        }
+    }
+
+    /// Window letters at which the wheel turns over its left neighbour.
+    fn notches(self) -> &'static [u8] {
+        match self {
+            Rotor::I => b"Q",
+            Rotor::II => b"E",
+            Rotor::III => b"V",
+            Rotor::IV => b"J",
+            Rotor::V => b"Z",
+            Rotor::VI | Rotor::VII | Rotor::VIII => b"ZM",
+            Rotor::Beta | Rotor::Gamma => b"",
+        }
+    }
+
+    fn is_greek(self) -> bool {
+        matches!(self, Rotor::Beta | Rotor::Gamma)
+    }
+
+    /// The wiring as letter indices, in both directions.
+    fn tables(self) -> ([u8; 26], [u8; 26]) {
+        let mut forward = [0; 26];
+        let mut backward = [0; 26];
+        for (i, &w) in self.wiring().iter().enumerate() {
+            forward[i] = w - b'A';
+            backward[(w - b'A') as usize] = i as u8;
+        }
+        (forward, backward)
+    }
+}
+
+impl fmt::Display for Rotor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Rotor::I => "I",
+            Rotor::II => "II",
+            Rotor::III => "III",
+            Rotor::IV => "IV",
+            Rotor::V => "V",
+            Rotor::VI => "VI",
+            Rotor::VII => "VII",
+            Rotor::VIII => "VIII",
+            Rotor::Beta => "Beta",
+            Rotor::Gamma => "Gamma",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Rotor {
+    type Err = PolygraphiaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Rotor::STEPPING
+            .into_iter()
+            .chain([Rotor::Beta, Rotor::Gamma])
+            .find(|rotor| rotor.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| PolygraphiaError::InvalidKey(format!("Unknown rotor '{s}'")))
+    }
+}
+
+/// Reflectors (Umkehrwalzen). The thin ones pair with a Greek wheel in the M4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Reflector {
+    B,
+    C,
+    BThin,
+    CThin,
+}
+
+impl Reflector {
+    fn wiring(self) -> &'static [u8; 26] {
+        match self {
+            Reflector::B => b"YRUHQSLDPXNGOKMIEBFZCWVJAT",
+            Reflector::C => b"FVPJIAOYEDRZXWGCTKUQSBNMHL",
+            Reflector::BThin => b"ENKQAUYWJICOPBLMDXZVFTHRGS",
+            Reflector::CThin => b"RDOBJNTKVEHMLFCWZAXGYIPSUQ",
+        }
+    }
+
+    fn is_thin(self) -> bool {
+        matches!(self, Reflector::BThin | Reflector::CThin)
+    }
+}
+
+/// The Enigma machine: three stepping rotors (plus a fixed Greek wheel on the
+/// M4), a reflector, ring settings and a plugboard, with the double stepping
+/// of the middle rotor.
+///
+/// Every message starts from the configured rotor positions, and the machine
+/// is its own inverse, so `encrypt` and `decrypt` do the same thing. Letters
+/// other than A-Z are passed through (or dropped in `TextMode::AlphaOnly`)
+/// without moving the rotors.
+#[derive(Debug, Clone)]
+pub struct Enigma {
+    /// Left to right, the Greek wheel first on the M4.
+    rotors: Vec<Rotor>,
+    reflector: Reflector,
+    /// Ring settings and starting positions, 0 for A, in the order of `rotors`.
+    pub(crate) rings: Vec<u8>,
+    pub(crate) positions: Vec<u8>,
+    /// The letter each letter is plugged to, itself if unplugged.
+    pub(crate) plugboard: [u8; 26],
+    mode: TextMode,
+    forward: Vec<[u8; 26]>,
+    backward: Vec<[u8; 26]>,
+}
+
+impl Enigma {
+    /// A three-rotor machine; `rotors` go from left to right and the reflector
+    /// must be B or C. Rings and positions start at A with no plugs.
+    pub fn new(rotors: [Rotor; 3], reflector: Reflector) -> Result<Self, PolygraphiaError> {
+        if reflector.is_thin() {
+            return Err(PolygraphiaError::InvalidKey(
+                "Thin reflectors need a Greek wheel; use Enigma::m4".to_string(),
+            ));
+        }
+        Self::build(rotors.to_vec(), reflector)
+    }
+
+    /// The four-rotor naval M4: a Greek wheel that never turns, three stepping
+    /// rotors and a thin reflector.
+    pub fn m4(
+        greek: Rotor,
+        rotors: [Rotor; 3],
+        reflector: Reflector,
+    ) -> Result<Self, PolygraphiaError> {
+        if !greek.is_greek() {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Rotor {greek} cannot be used as the Greek wheel"
+            )));
+        }
+        if !reflector.is_thin() {
+            return Err(PolygraphiaError::InvalidKey(
+                "The M4 needs a thin reflector".to_string(),
+            ));
+        }
+        let mut wheels = vec![greek];
+        wheels.extend(rotors);
+        Self::build(wheels, reflector)
+    }
+
+    pub fn random(plugs: usize) -> Result<Self, PolygraphiaError> {
+        Self::random_with(plugs, &mut KeyGenerator::new())
+    }
+
+    /// A three-rotor machine with three different stepping rotors, reflector B
+    /// or C, random rings and positions and `plugs` plugboard pairs, at most 13.
+    pub fn random_with(
+        plugs: usize,
+        generator: &mut KeyGenerator,
+    ) -> Result<Self, PolygraphiaError> {
+        if plugs > 13 {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "The plugboard holds at most 13 pairs, got {plugs}"
+            )));
+        }
+        let mut wheels = Rotor::STEPPING;
+        wheels.shuffle(generator.rng());
+        let reflector = if generator.rng().random() {
+            Reflector::B
+        } else {
+            Reflector::C
+        };
+        let mut enigma = Self::new([wheels[0], wheels[1], wheels[2]], reflector)?;
+
+        let letters = Alphabet::english();
+        enigma.set_ring_settings(&generator.word(&letters, 3))?;
+        enigma.set_positions(&generator.word(&letters, 3))?;
+        let plugged: Vec<char> = generator.permutation(&letters).chars().collect();
+        let pairs: Vec<String> = plugged[..2 * plugs]
+            .chunks(2)
+            .map(|pair| pair.iter().collect())
+            .collect();
+        enigma.set_plugboard(&pairs.join(" "))?;
+        Ok(enigma)
+    }
+
+    fn build(rotors: Vec<Rotor>, reflector: Reflector) -> Result<Self, PolygraphiaError> {
+        let stepping = &rotors[rotors.len() - 3..];
+        if let Some(greek) = stepping.iter().find(|rotor| rotor.is_greek()) {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Greek wheel {greek} cannot be used as a stepping rotor"
+            )));
+        }
+        if stepping[0] == stepping[1] || stepping[0] == stepping[2] || stepping[1] == stepping[2] {
+            return Err(PolygraphiaError::InvalidKey(
+                "Each rotor can only be used once".to_string(),
+            ));
+        }
+        let count = rotors.len();
+        let (forward, backward) = rotors.iter().map(|rotor| rotor.tables()).unzip();
+        Ok(Enigma {
+            forward,
+            backward,
+            rotors,
+            reflector,
+            rings: vec![0; count],
+            positions: vec![0; count],
+            plugboard: std::array::from_fn(|i| i as u8),
+            mode: TextMode::default(),
+        })
+    }
+
+    pub fn rotors(&self) -> &[Rotor] {
+        &self.rotors
+    }
+
+    pub fn reflector(&self) -> Reflector {
+        self.reflector
+    }
+
+    pub fn ring_settings(&self) -> String {
+        Self::letters(&self.rings)
+    }
+
+    /// Starting positions as shown in the windows, e.g. `"BLA"`.
+    pub fn positions(&self) -> String {
+        Self::letters(&self.positions)
+    }
+
+    /// Plugged pairs in alphabetical order, e.g. `"AV BS CG"`.
+    pub fn plugboard(&self) -> String {
+        (0..26u8)
+            .filter(|&a| self.plugboard[a as usize] > a)
+            .map(|a| Self::letters(&[a, self.plugboard[a as usize]]))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    /// One letter per rotor, left to right: ring setting 01 is `A`, 02 is `B`.
+    pub fn set_ring_settings(&mut self, rings: &str) -> Result<(), PolygraphiaError> {
+        self.rings = self.parse_letters(rings, "ring settings")?;
+        Ok(())
+    }
+
+    /// One letter per rotor, left to right.
+    pub fn set_positions(&mut self, positions: &str) -> Result<(), PolygraphiaError> {
+        self.positions = self.parse_letters(positions, "rotor positions")?;
+        Ok(())
+    }
+
+    /// Letter pairs separated by spaces, e.g. `"AV BS CG DL"`; an empty string
+    /// removes every plug.
+    pub fn set_plugboard(&mut self, pairs: &str) -> Result<(), PolygraphiaError> {
+        let mut plugboard: [u8; 26] = std::array::from_fn(|i| i as u8);
+        for pair in pairs.split_whitespace() {
+            let letters: Vec<u8> = pair.chars().filter_map(Self::letter_index).collect();
+            if letters.len() != 2 || pair.chars().count() != 2 || letters[0] == letters[1] {
+                return Err(PolygraphiaError::InvalidKey(format!(
+                    "Plugboard pair '{pair}' must be two different letters"
+                )));
+            }
+            let (a, b) = (letters[0] as usize, letters[1] as usize);
+            if plugboard[a] != a as u8 || plugboard[b] != b as u8 {
+                return Err(PolygraphiaError::InvalidKey(format!(
+                    "Plugboard pair '{pair}' reuses a letter that is already plugged"
+                )));
+            }
+            plugboard[a] = b as u8;
+            plugboard[b] = a as u8;
+        }
+        self.plugboard = plugboard;
+        Ok(())
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    fn letter_index(c: char) -> Option<u8> {
+        c.is_ascii_alphabetic()
+            .then(|| c.to_ascii_uppercase() as u8 - b'A')
+    }
+
+    fn letters(indices: &[u8]) -> String {
+        indices.iter().map(|&i| (b'A' + i) as char).collect()
+    }
+
+    fn parse_letters(&self, text: &str, what: &str) -> Result<Vec<u8>, PolygraphiaError> {
+        let indices: Option<Vec<u8>> = text.chars().map(Self::letter_index).collect();
+        match indices {
+            Some(indices) if indices.len() == self.rotors.len() => Ok(indices),
+            _ => Err(PolygraphiaError::InvalidKey(format!(
+                "Expected {} letters for the {what}, got '{text}'",
+                self.rotors.len()
+            ))),
+        }
+    }
+
+    /// Turns the rotors before a key press. The middle rotor steps when the
+    /// right one is at its notch and also, together with the left one, when
+    /// it is at its own notch itself.
+    fn step(&self, positions: &mut [u8]) {
+        let n = positions.len();
+        let at_notch = |slot: usize, positions: &[u8]| {
+            self.rotors[slot]
+                .notches()
+                .contains(&(b'A' + positions[slot]))
+        };
+        if at_notch(n - 2, positions) {
+            positions[n - 3] = (positions[n - 3] + 1) % 26;
+            positions[n - 2] = (positions[n - 2] + 1) % 26;
+        } else if at_notch(n - 1, positions) {
+            positions[n - 2] = (positions[n - 2] + 1) % 26;
+        }
+        positions[n - 1] = (positions[n - 1] + 1) % 26;
+    }
+
+    fn through_rotor(&self, slot: usize, positions: &[u8], c: u8, forward: bool) -> u8 {
+        let offset = (26 + positions[slot] - self.rings[slot]) % 26;
+        let table = if forward {
+            &self.forward[slot]
+        } else {
+            &self.backward[slot]
+        };
+        (table[((c + offset) % 26) as usize] + 26 - offset) % 26
+    }
+
+    /// Enciphers one letter index at the given (already stepped) positions.
+    fn press(&self, positions: &[u8], c: u8) -> u8 {
+        let mut c = self.plugboard[c as usize];
+        for slot in (0..self.rotors.len()).rev() {
+            c = self.through_rotor(slot, positions, c, true);
+        }
+        c = self.reflector.wiring()[c as usize] - b'A';
+        for slot in 0..self.rotors.len() {
+            c = self.through_rotor(slot, positions, c, false);
+        }
+        self.plugboard[c as usize]
+    }
+
+    /// Enciphers letter indices (0 for A) from the starting positions.
+    pub(crate) fn process_indices(&self, letters: &[u8], output: &mut Vec<u8>) {
+        let mut window = [0; 4];
+        let positions = &mut window[..self.positions.len()];
+        positions.copy_from_slice(&self.positions);
+        output.clear();
+        for &c in letters {
+            self.step(positions);
+            output.push(self.press(positions, c));
+        }
+    }
+
+    /// Enciphers one character at `positions`, stepping them for letters;
+    /// `None` if it is dropped.
+    fn map_char(&self, c: char, positions: &mut [u8]) -> Option<char> {
+        let Some(index) = Self::letter_index(c) else {
+            return (self.mode == TextMode::PreserveAll).then_some(c);
+        };
+        self.step(positions);
+        let letter = (b'A' + self.press(positions, index)) as char;
+        Some(if c.is_ascii_lowercase() {
+            letter.to_ascii_lowercase()
+        } else {
+            letter
+        })
+    }
+
+    /// Enciphers `text` from `positions`, leaving them where the message ends.
+    fn process_text(&self, text: &str, positions: &mut [u8], out: &mut String) {
+        out.extend(text.chars().filter_map(|c| self.map_char(c, positions)));
+    }
+
+    fn process_in_place(&self, data: &mut [u8], what: &str) -> Result<(), PolygraphiaError> {
+        if let Ok(text) = std::str::from_utf8(data) {
+            self.validate_input(text, what)?;
+        }
+        text::map_in_place(data, self.name(), what, || {
+            let mut window = [0; 4];
+            let n = self.positions.len();
+            window[..n].copy_from_slice(&self.positions);
+            move |c| self.map_char(c, &mut window[..n])
+        })
+    }
+
+    fn process_message(&self, text: &str) -> String {
+        let mut positions = self.positions.clone();
+        let mut result = String::with_capacity(text.len());
+        self.process_text(text, &mut positions, &mut result);
+        positions.fill(0);
+        result
+    }
+
+    /// The rotor positions a streamed message has reached.
+    fn stream_positions<'a>(&self, state: &'a mut EnigmaState) -> &'a mut [u8] {
+        if state.positions.is_empty() {
+            state.positions = self.positions.clone();
+        }
+        &mut state.positions
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
+        }
+        if !text.chars().any(|c| c.is_ascii_alphabetic()) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one letter A-Z"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for Enigma {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        Ok(self.process_message(plaintext))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        Ok(self.process_message(ciphertext))
+    }
+
+    fn encrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        self.process_in_place(data, "Plaintext")
+    }
+
+    fn decrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        self.process_in_place(data, "Ciphertext")
+    }
+
+    fn name(&self) -> &str {
+        "enigma"
+    }
+}
+
+/// Stream state of `Enigma`: the rotor positions reached so far.
+#[derive(Debug, Default, Clone)]
+pub struct EnigmaState {
+    scan: BlockState,
+    positions: Vec<u8>,
+}
+
+impl Drop for EnigmaState {
+    fn drop(&mut self) {
+        self.positions.fill(0);
+    }
+}
+
+impl StreamCipher for Enigma {
+    type State = EnigmaState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut EnigmaState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.scan.cut(text, 1, |c| c.is_ascii_alphabetic());
+        self.process_text(&text[..cut], self.stream_positions(state), out);
+        Ok(cut)
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut EnigmaState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        self.encrypt_partial(text, state, out)
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        state: &mut EnigmaState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Plaintext")?;
+        self.process_text(text, self.stream_positions(state), out);
+        Ok(())
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        state: &mut EnigmaState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Ciphertext")?;
+        self.process_text(text, self.stream_positions(state), out);
+        Ok(())
+    }
+}
+
+impl Drop for Enigma {
+    fn drop(&mut self) {
+        self.rings.fill(0);
+        self.positions.fill(0);
+        self.plugboard.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grouped(text: &str) -> String {
+        text.chars().filter(|c| !c.is_whitespace()).collect()
+    }
+
+    #[test]
+    fn test_enigma_default_settings() {
+        let cipher = Enigma::new([Rotor::I, Rotor::II, Rotor::III], Reflector::B).unwrap();
+        assert_eq!(cipher.encrypt("AAAAA").unwrap(), "BDZGO");
+        assert_eq!(cipher.decrypt("BDZGO").unwrap(), "AAAAA");
+    }
+
+    #[test]
+    fn test_enigma_double_stepping() {
+        let mut cipher = Enigma::new([Rotor::I, Rotor::II, Rotor::III], Reflector::B).unwrap();
+        cipher.set_positions("ADU").unwrap();
+        let mut positions = cipher.positions.clone();
+        let mut windows = Vec::new();
+        for _ in 0..3 {
+            cipher.step(&mut positions);
+            windows.push(Enigma::letters(&positions));
+        }
+        assert_eq!(windows, ["ADV", "AEW", "BFX"]);
+    }
+
+    #[test]
+    fn test_enigma_barbarossa() {
+        // Operation Barbarossa, 7 July 1941: rotors II IV V, reflector B,
+        // rings 02 21 12, indicator WXC KCH giving the message key BLA
+        let mut cipher = Enigma::new([Rotor::II, Rotor::IV, Rotor::V], Reflector::B).unwrap();
+        cipher.set_ring_settings("BUL").unwrap();
+        cipher
+            .set_plugboard("AV BS CG DL FU HZ IN KM OW RX")
+            .unwrap();
+        cipher.set_positions("WXC").unwrap();
+        assert_eq!(cipher.decrypt("KCH").unwrap(), "BLA");
+
+        cipher.set_positions("BLA").unwrap();
+        let ciphertext = grouped(
+            "EDPUD NRGYS ZRCXN UYTPO MRMBO FKTBZ REZKM LXLVE FGUEY SIOZV EQMIK UBPMM \
+             YLKLT TDEIS MDICA GYKUA CTCDO MOHWX MUUIA UBSTS LRNBZ SZWNR FXWFY SSXJZ \
+             VIJHI DISHP RKLKA YUPAD TXQSP INQMA TLPIF SVKDA SCTAC DPBOP VHJK",
+        );
+        let plaintext = grouped(
+            "AUFKL XABTE ILUNG XVONX KURTI NOWAX KURTI NOWAX NORDW ESTLX SEBEZ XSEBE \
+             ZXUAF FLIEG ERSTR ASZER IQTUN GXDUB ROWKI XDUBR OWKIX OPOTS CHKAX OPOTS \
+             CHKAX UMXEI NSAQT DREIN ULLXU HRANG ETRET ENXAN GRIFF XINFX RGTX",
+        );
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), plaintext);
+        assert_eq!(cipher.encrypt(&plaintext).unwrap(), ciphertext);
+    }
+
+    #[test]
+    fn test_enigma_m4() {
+        // Naval M4 message: Beta II IV I, thin reflector B, rings AAAV
+        let mut cipher = Enigma::m4(
+            Rotor::Beta,
+            [Rotor::II, Rotor::IV, Rotor::I],
+            Reflector::BThin,
+        )
+        .unwrap();
+        cipher.set_ring_settings("AAAV").unwrap();
+        cipher.set_positions("VJNA").unwrap();
+        cipher
+            .set_plugboard("AT BL DF GJ HM NW OP QY RZ VX")
+            .unwrap();
+        let ciphertext = grouped(
+            "NCZW VUSX PNYM INHZ XMQX SFWX WLKJ AHSH NMCO CCAK UQPM KCSM HKSE INJU \
+             SBLK IOSX CKUB HMLL XCSJ USRR DVKO HULX WCCB GVLI YXEO AHXR HKKF VDRE \
+             WEZL XOBA FGYU JQUK GRTV UKAM EURB VEKS UHHV OYHA BCJW MAKL FKLM YFVN \
+             RIZR VVRT KOFD ANJM OLBG FFLE OPRG TFLV RHOW OPBE KVWM UQFM PWPA RMFH \
+             AGKX IIBG",
+        );
+        let plaintext = grouped(
+            "VONV ONJL OOKS JHFF TTTE INSE INSD REIZ WOYY QNNS NEUN INHA LTXX BEIA \
+             NGRI FFUN TERW ASSE RGED RUEC KTYW ABOS XLET ZTER GEGN ERST ANDN ULAC \
+             HTDR EINU LUHR MARQ UANT ONJO TANE UNAC HTSE YHSD REIY ZWOZ WONU LGRA \
+             DYAC HTSM YSTO SSEN ACHX EKNS VIER MBFA ELLT YNNN NNNO OOVI ERYS ICHT \
+             EINS NULL",
+        );
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_enigma_text_modes() {
+        let mut cipher = Enigma::new([Rotor::I, Rotor::II, Rotor::III], Reflector::B).unwrap();
+        assert_eq!(cipher.encrypt("aa, aA!").unwrap(), "bd, zG!");
+
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert_eq!(cipher.encrypt("aa, aA!").unwrap(), "bdzG");
+        assert_eq!(cipher.name(), "enigma");
+
+        let boxed: Box<dyn Cipher> = Box::new(cipher);
+        assert_eq!(boxed.decrypt("bdzG").unwrap(), "aaaA");
+    }
+
+    #[test]
+    fn test_enigma_settings() {
+        let mut cipher = Enigma::new([Rotor::VI, Rotor::VII, Rotor::VIII], Reflector::C).unwrap();
+        cipher.set_plugboard("qa ZP").unwrap();
+        assert_eq!(cipher.plugboard(), "AQ PZ");
+        cipher.set_ring_settings("xyz").unwrap();
+        assert_eq!(cipher.ring_settings(), "XYZ");
+        assert_eq!(cipher.rotors()[2].to_string(), "VIII");
+        assert_eq!("viii".parse::<Rotor>().unwrap(), Rotor::VIII);
+
+        let ciphertext = cipher.encrypt("Attack at dawn").unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Attack at dawn");
+    }
+
+    #[test]
+    fn test_enigma_bytes_in_place() {
+        let mut cipher = Enigma::new([Rotor::I, Rotor::II, Rotor::III], Reflector::B).unwrap();
+        cipher.set_positions("QEV").unwrap();
+        let mut data = b"Attack at dawn, 44!".to_vec();
+        let (ptr, capacity) = (data.as_ptr(), data.capacity());
+        cipher.encrypt_bytes_in_place(&mut data).unwrap();
+        assert_eq!(
+            data,
+            cipher.encrypt("Attack at dawn, 44!").unwrap().as_bytes()
+        );
+        assert_eq!((data.as_ptr(), data.capacity()), (ptr, capacity));
+        cipher.decrypt_bytes_in_place(&mut data).unwrap();
+        assert_eq!(data, b"Attack at dawn, 44!");
+
+        assert!(
+            cipher
+                .encrypt_bytes_in_place(&mut b"1234".to_vec())
+                .is_err()
+        );
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert!(cipher.encrypt_bytes_in_place(&mut data).is_err());
+        assert_eq!(data, b"Attack at dawn, 44!");
+    }
+
+    #[test]
+    fn test_enigma_random() {
+        let mut generator = KeyGenerator::from_seed(8);
+        let enigma = Enigma::random_with(10, &mut generator).unwrap();
+        assert_eq!(enigma.plugboard().split_whitespace().count(), 10);
+        let ciphertext = enigma.encrypt("Attack at dawn").unwrap();
+        assert_eq!(enigma.decrypt(&ciphertext).unwrap(), "Attack at dawn");
+
+        let a = Enigma::random_with(13, &mut KeyGenerator::from_seed(1)).unwrap();
+        let b = Enigma::random_with(13, &mut KeyGenerator::from_seed(1)).unwrap();
+        assert_eq!(a.rotors(), b.rotors());
+        assert_eq!(a.positions(), b.positions());
+        assert_eq!(a.plugboard(), b.plugboard());
+        assert!(Enigma::random(0).is_ok());
+        assert!(Enigma::random(14).is_err());
+    }
+
+    #[test]
+    fn test_enigma_invalid() {
+        let rotors = [Rotor::I, Rotor::II, Rotor::III];
+        assert!(Enigma::new([Rotor::I, Rotor::I, Rotor::III], Reflector::B).is_err());
+        assert!(Enigma::new([Rotor::Beta, Rotor::I, Rotor::III], Reflector::B).is_err());
+        assert!(Enigma::new(rotors, Reflector::BThin).is_err());
+        assert!(Enigma::m4(Rotor::IV, rotors, Reflector::BThin).is_err());
+        assert!(Enigma::m4(Rotor::Gamma, rotors, Reflector::B).is_err());
+        assert!("IX".parse::<Rotor>().is_err());
+
+        let mut cipher = Enigma::new(rotors, Reflector::B).unwrap();
+        assert!(cipher.set_positions("AB").is_err());
+        assert!(cipher.set_ring_settings("A1C").is_err());
+        assert!(cipher.set_plugboard("AB BC").is_err());
+        assert!(cipher.set_plugboard("AA").is_err());
+        assert!(cipher.set_plugboard("ABC").is_err());
+        assert!(cipher.encrypt("1234").is_err());
+    }
+}
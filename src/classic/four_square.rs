@@ -0,0 +1,305 @@
+use crate::error::PolygraphiaError;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::digraph::{DoubledLetters, PairRules, PairState};
+use crate::utils::padding::{Padding, Unpadding};
+use crate::utils::{Alphabet, KeyGenerator, PolybiusSquare, TextMode};
+
+/// Delastelle's Four-square cipher: the letters of each pair are found in two
+/// plain squares, and the other corners of their rectangle are read from two
+/// keyed squares.
+///
+/// Doubled letters need no separator, so they are kept by default.
+#[derive(Debug, Clone)]
+pub struct FourSquare {
+    plain: PolybiusSquare,
+    first: PolybiusSquare,
+    second: PolybiusSquare,
+    pairs: PairRules,
+}
+
+impl FourSquare {
+    pub fn new(first_key: &str, second_key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(first_key, second_key, Alphabet::english_merged_ij())
+    }
+
+    pub fn with_alphabet(
+        first_key: &str,
+        second_key: &str,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        if first_key.is_empty() || second_key.is_empty() {
+            return Err(PolygraphiaError::InvalidKey(
+                "Keys cannot be empty".to_string(),
+            ));
+        }
+        Ok(FourSquare {
+            plain: PolybiusSquare::new("", alphabet.clone())?,
+            first: PolybiusSquare::new(first_key, alphabet.clone())?,
+            second: PolybiusSquare::new(second_key, alphabet.clone())?,
+            pairs: PairRules::new(&alphabet, TextMode::default(), DoubledLetters::Keep),
+        })
+    }
+
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        Self::random_with(&mut KeyGenerator::new(), Alphabet::english_merged_ij())
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let first_key = generator.permutation(&alphabet);
+        let second_key = generator.permutation(&alphabet);
+        Self::with_alphabet(&first_key, &second_key, alphabet)
+    }
+
+    /// The keyed square at the top right.
+    pub fn first_key(&self) -> &str {
+        self.first.key()
+    }
+
+    /// The keyed square at the bottom left.
+    pub fn second_key(&self) -> &str {
+        self.second.key()
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        self.plain.alphabet()
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.pairs.mode
+    }
+
+    pub fn filler(&self) -> char {
+        self.pairs.filler
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.pairs.padding
+    }
+
+    pub fn unpadding(&self) -> Unpadding {
+        self.pairs.unpadding
+    }
+
+    pub fn doubled_letters(&self) -> DoubledLetters {
+        self.pairs.doubled
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.pairs.mode = mode;
+    }
+
+    pub fn set_filler(&mut self, filler: char) -> Result<(), PolygraphiaError> {
+        self.pairs.set_filler(filler, self.plain.alphabet())
+    }
+
+    pub fn set_padding(&mut self, padding: Padding) {
+        self.pairs.padding = padding;
+    }
+
+    pub fn set_unpadding(&mut self, unpadding: Unpadding) {
+        self.pairs.unpadding = unpadding;
+    }
+
+    pub fn set_doubled_letters(&mut self, doubled: DoubledLetters) {
+        self.pairs.doubled = doubled;
+    }
+
+    fn process_pair(&self, first: char, second: char, encrypt: bool) -> (char, char) {
+        let (from_first, from_second, to_first, to_second) = if encrypt {
+            (&self.plain, &self.plain, &self.first, &self.second)
+        } else {
+            (&self.first, &self.second, &self.plain, &self.plain)
+        };
+        let (row1, col1) = from_first.coordinates(first).expect("letter in alphabet");
+        let (row2, col2) = from_second.coordinates(second).expect("letter in alphabet");
+        (
+            to_first.symbol_at(row1, col2),
+            to_second.symbol_at(row2, col1),
+        )
+    }
+
+    fn process_text(
+        &self,
+        text: &str,
+        encrypt: bool,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.pairs.process_into(
+            text,
+            self.plain.alphabet(),
+            encrypt,
+            out,
+            |first, second| Ok(self.process_pair(first, second, encrypt)),
+        )
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
+        }
+        if !text.chars().any(|c| self.plain.alphabet().contains(c)) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one alphabetic character"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for FourSquare {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(plaintext.len() + 2);
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        self.process_text(plaintext, true, out)
+    }
+
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        self.process_text(ciphertext, false, out)
+    }
+
+    fn name(&self) -> &str {
+        "four-square"
+    }
+}
+
+impl StreamCipher for FourSquare {
+    type State = PairState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        self.pairs.process_partial(
+            text,
+            self.plain.alphabet(),
+            true,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second, true)),
+        )
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        self.pairs.process_partial(
+            text,
+            self.plain.alphabet(),
+            false,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second, false)),
+        )
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Plaintext")?;
+        self.pairs.process_final(
+            text,
+            self.plain.alphabet(),
+            true,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second, true)),
+        )
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Ciphertext")?;
+        self.pairs.process_final(
+            text,
+            self.plain.alphabet(),
+            false,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second, false)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_four_square_known_answer() {
+        // 25 letters without 'q'
+        let alphabet = Alphabet::new("abcdefghijklmnoprstuvwxyz").unwrap();
+        let mut cipher = FourSquare::with_alphabet("example", "keyword", alphabet).unwrap();
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert_eq!(
+            cipher.encrypt("help me obi wan kenobi").unwrap(),
+            "fygmkyhobxmfkkkimd"
+        );
+        assert_eq!(
+            cipher.decrypt("FYGMKYHOBXMFKKKIMD").unwrap(),
+            "helpmeobiwankenobi"
+        );
+    }
+
+    #[test]
+    fn test_four_square_pairs() {
+        let mut cipher = FourSquare::new("zebras", "dingo").unwrap();
+        assert_eq!(cipher.doubled_letters(), DoubledLetters::Keep);
+        assert_eq!(cipher.first_key().len(), 25);
+
+        let ciphertext = cipher.encrypt("Meet me, Bob!").unwrap();
+        assert_eq!(ciphertext.len(), "Meet me, Bob!".len() + 1);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Meet me, Bob!x");
+
+        cipher.set_unpadding(Unpadding::Heuristic);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Meet me, Bob!");
+
+        cipher.set_doubled_letters(DoubledLetters::Separate);
+        cipher.set_mode(TextMode::AlphaOnly);
+        let ciphertext = cipher.encrypt("meet").unwrap();
+        assert_eq!(ciphertext.len(), 6);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "meet");
+    }
+
+    #[test]
+    fn test_four_square_random_and_invalid() {
+        let mut generator = KeyGenerator::from_seed(7);
+        let cipher =
+            FourSquare::random_with(&mut generator, Alphabet::english_merged_ij()).unwrap();
+        assert_ne!(cipher.first_key(), cipher.second_key());
+        let ciphertext = cipher.encrypt("Attack at dawn").unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Attack at dawn");
+
+        assert!(FourSquare::new("", "key").is_err());
+        assert!(cipher.encrypt("1234").is_err());
+        assert_eq!(cipher.name(), "four-square");
+    }
+}
@@ -1,13 +1,19 @@
 use crate::error::PolygraphiaError;
-use crate::traits::Cipher;
-use crate::utils::{Matrix, TextMode};
+use crate::stream::BlockState;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::padding::{self, Padding, Unpadding};
+use crate::utils::{Alphabet, KeyGenerator, Matrix, TextMode, text};
 
 #[derive(Debug, Clone)]
 pub struct Hill {
     key: Matrix,
     inv_key: Matrix,
     key_size: usize,
+    alphabet: Alphabet,
     mode: TextMode,
+    filler: char,
+    padding: Padding,
+    unpadding: Unpadding,
 }
 
 impl Hill {
@@ -15,6 +21,31 @@ impl Hill {
         Self::with_mode(key, TextMode::default())
     }
 
+    pub fn random_invertible(size: usize) -> Result<Self, PolygraphiaError> {
+        Self::random_invertible_with(size, &mut KeyGenerator::new(), Alphabet::default())
+    }
+
+    pub fn random_invertible_with(
+        size: usize,
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let matrix = generator.invertible_matrix(size, alphabet.len() as u64)?;
+        Self::from_matrix(&matrix, alphabet)
+    }
+
+    pub fn from_matrix(matrix: &Matrix, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        let size = matrix.size();
+        let mut key = String::with_capacity(size * size);
+        for row in 0..size {
+            for col in 0..size {
+                let idx = matrix.get(row, col) % alphabet.len() as u64;
+                key.push(alphabet.symbol(idx as usize));
+            }
+        }
+        Self::with_alphabet(&key, alphabet)
+    }
+
     pub fn key(&self) -> &Matrix {
         &self.key
     }
@@ -27,12 +58,28 @@ impl Hill {
         self.key_size
     }
 
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
     pub fn mode(&self) -> TextMode {
         self.mode
     }
 
+    pub fn filler(&self) -> char {
+        self.filler
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.padding
+    }
+
+    pub fn unpadding(&self) -> Unpadding {
+        self.unpadding
+    }
+
     pub fn set_key(&mut self, key: &str) -> Result<(), PolygraphiaError> {
-        let (key_matrix, inv_key_matrix, key_size) = Self::prepare_key(key)?;
+        let (key_matrix, inv_key_matrix, key_size) = Self::prepare_key(key, &self.alphabet)?;
         self.key = key_matrix;
         self.inv_key = inv_key_matrix;
         self.key_size = key_size;
@@ -43,109 +90,181 @@ impl Hill {
         self.mode = mode;
     }
 
+    pub fn set_filler(&mut self, filler: char) -> Result<(), PolygraphiaError> {
+        self.filler = padding::validate_filler(filler, &self.alphabet)?;
+        Ok(())
+    }
+
+    pub fn set_padding(&mut self, padding: Padding) {
+        self.padding = padding;
+    }
+
+    pub fn set_unpadding(&mut self, unpadding: Unpadding) {
+        self.unpadding = unpadding;
+    }
+
     pub fn with_mode(key: &str, mode: TextMode) -> Result<Self, PolygraphiaError> {
-        let (key_matrix, inv_key_matrix, key_size) = Self::prepare_key(key)?;
+        Self::with_alphabet_and_mode(key, Alphabet::default(), mode)
+    }
+
+    pub fn with_alphabet(key: &str, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet_and_mode(key, alphabet, TextMode::default())
+    }
+
+    pub fn with_alphabet_and_mode(
+        key: &str,
+        alphabet: Alphabet,
+        mode: TextMode,
+    ) -> Result<Self, PolygraphiaError> {
+        let (key_matrix, inv_key_matrix, key_size) = Self::prepare_key(key, &alphabet)?;
         Ok(Hill {
             key: key_matrix,
             inv_key: inv_key_matrix,
             key_size,
+            filler: padding::default_filler(&alphabet),
+            alphabet,
             mode,
+            padding: Padding::default(),
+            unpadding: Unpadding::default(),
         })
     }
 
-    fn prepare_key(key: &str) -> Result<(Matrix, Matrix, usize), PolygraphiaError> {
-        let key_clean: String = key
+    fn prepare_key(
+        key: &str,
+        alphabet: &Alphabet,
+    ) -> Result<(Matrix, Matrix, usize), PolygraphiaError> {
+        let matrix_data: Vec<u64> = key
             .chars()
-            .filter(|c| c.is_ascii_alphabetic())
-            .map(|c| c.to_ascii_lowercase())
+            .filter_map(|c| alphabet.index_of(c))
+            .map(|idx| idx as u64)
             .collect();
-        let key_size = (key_clean.len() as f64).sqrt() as usize;
-        if key_size * key_size != key_clean.len() {
+        let key_size = (matrix_data.len() as f64).sqrt() as usize;
+        if key_size == 0 || key_size * key_size != matrix_data.len() {
             return Err(PolygraphiaError::InvalidKey(format!(
                 "Key length {} must be a perfect square (4, 9, 16, 25, ...)",
-                key_clean.len()
+                matrix_data.len()
             )));
         }
-        let matrix_data: Vec<i32> = key_clean.chars().map(|c| (c as u8 - b'a') as i32).collect();
         let key_matrix = Matrix::new(key_size, matrix_data)?;
-        let inv_key_matrix = Self::validate_key(&key_matrix)?;
+        let inv_key_matrix = Self::validate_key(&key_matrix, alphabet)?;
         Ok((key_matrix, inv_key_matrix, key_size))
     }
 
-    fn validate_key(matrix: &Matrix) -> Result<Matrix, PolygraphiaError> {
-        matrix.mod_inverse(26)
+    fn validate_key(matrix: &Matrix, alphabet: &Alphabet) -> Result<Matrix, PolygraphiaError> {
+        matrix.mod_inverse(alphabet.len() as u64)
     }
 
-    fn prepare_text(&self, text: &str) -> String {
-        let mut clean: String = text
+    fn prepare_text(
+        &self,
+        text: &str,
+        encrypt: bool,
+        finish: bool,
+    ) -> Result<Vec<char>, PolygraphiaError> {
+        let mut letters: Vec<char> = text
             .chars()
-            .filter(|c| c.is_ascii_alphabetic())
-            .map(|c| c.to_ascii_lowercase())
+            .filter(|&c| self.alphabet.contains(c))
             .collect();
-        let remainder = clean.len() % self.key_size;
-        if remainder != 0 {
-            let padding_needed = self.key_size - remainder;
-            clean.push_str(&"x".repeat(padding_needed));
+        if !finish {
+            return Ok(letters);
         }
-        clean
-    }
-
-    fn text_to_vector(text: &str) -> Vec<i32> {
-        text.chars()
-            .map(|c| (c.to_ascii_lowercase() as u8 - b'a') as i32)
-            .collect()
-    }
-
-    fn vector_to_text(vector: &[i32]) -> String {
-        vector
-            .iter()
-            .map(|&x| {
-                let val = x.rem_euclid(26) as u8;
-                (b'a' + val) as char
-            })
-            .collect()
-    }
-
-    fn process_text(&self, text: &str, encrypt: bool) -> String {
-        let prepared = self.prepare_text(text);
-        let vector = Self::text_to_vector(&prepared);
+        let padding = if encrypt {
+            self.padding
+        } else {
+            Padding::Filler
+        };
+        padding::pad(
+            &mut letters,
+            self.key_size,
+            self.filler,
+            padding,
+            &self.alphabet,
+        )?;
+        Ok(letters)
+    }
+
+    /// Processes `text`, padding or unpadding it only when `finish` is set;
+    /// otherwise it must hold whole blocks.
+    fn process_text(
+        &self,
+        text: &str,
+        encrypt: bool,
+        finish: bool,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        let mut letters = self.prepare_text(text, encrypt, finish)?;
         let matrix = if encrypt { &self.key } else { &self.inv_key };
-        let mut result = Vec::new();
-        for chunk in vector.chunks(self.key_size) {
-            let encrypted_chunk = matrix.multiply_vector(chunk);
-            result.extend(encrypted_chunk.iter().map(|&x| x.rem_euclid(26)));
+        let modulus = self.alphabet.len() as u64;
+        let mut block = vec![0; 2 * self.key_size];
+        let (input, output) = block.split_at_mut(self.key_size);
+        for chunk in letters.chunks_exact_mut(self.key_size) {
+            for (x, &c) in input.iter_mut().zip(chunk.iter()) {
+                *x = self.alphabet.index_of(c).unwrap_or(0) as u64;
+            }
+            matrix.multiply_vector_into(input, modulus, output);
+            for (slot, &x) in chunk.iter_mut().zip(output.iter()) {
+                *slot = match self.mode {
+                    TextMode::PreserveAll => self.alphabet.symbol_like(x as usize, *slot),
+                    TextMode::AlphaOnly => self.alphabet.symbol(x as usize),
+                };
+            }
+        }
+        block.fill(0);
+        if !encrypt && finish {
+            padding::unpad(
+                &mut letters,
+                self.key_size,
+                self.filler,
+                self.unpadding,
+                &self.alphabet,
+            )?;
+        }
+        match self.mode {
+            TextMode::PreserveAll => text::restore_layout_into(text, &letters, &self.alphabet, out),
+            TextMode::AlphaOnly => out.extend(letters),
         }
-        Self::vector_to_text(&result)
+        Ok(())
     }
 }
 
 impl Cipher for Hill {
     fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(plaintext.len() + self.key_size);
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
         if plaintext.is_empty() {
             return Err(PolygraphiaError::InvalidInput(
                 "Plaintext cannot be empty".to_string(),
             ));
         }
-        if !plaintext.chars().any(|c| c.is_ascii_alphabetic()) {
+        if !plaintext.chars().any(|c| self.alphabet.contains(c)) {
             return Err(PolygraphiaError::InvalidInput(
                 "Plaintext must contain at least one alphabetic character".to_string(),
             ));
         }
-        Ok(self.process_text(plaintext, true))
+        self.process_text(plaintext, true, true, out)
     }
 
-    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
         if ciphertext.is_empty() {
             return Err(PolygraphiaError::InvalidInput(
                 "Ciphertext cannot be empty".to_string(),
             ));
         }
-        if !ciphertext.chars().any(|c| c.is_ascii_alphabetic()) {
+        if !ciphertext.chars().any(|c| self.alphabet.contains(c)) {
             return Err(PolygraphiaError::InvalidInput(
                 "Ciphertext must contain at least one alphabetic character".to_string(),
             ));
         }
-        Ok(self.process_text(ciphertext, false))
+        self.process_text(ciphertext, false, true, out)
     }
 
     fn name(&self) -> &str {
@@ -153,6 +272,50 @@ impl Cipher for Hill {
     }
 }
 
+impl StreamCipher for Hill {
+    type State = BlockState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, self.key_size, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], true, false, out)?;
+        Ok(cut)
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, self.key_size, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], false, false, out)?;
+        Ok(cut)
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.encrypt_into(text, out)
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.decrypt_into(text, out)
+    }
+}
+
 impl Drop for Hill {
     fn drop(&mut self) {
         // the matrix will clean automatically when it goes out of scope
@@ -225,9 +388,134 @@ mod tests {
         assert_eq!(decrypted, "catx");
     }
 
+    #[test]
+    fn test_hill_custom_filler() {
+        let mut cipher = Hill::with_mode("hill", TextMode::AlphaOnly).unwrap();
+        cipher.set_filler('Z').unwrap();
+        assert_eq!(cipher.filler(), 'z');
+
+        let encrypted = cipher.encrypt("cat").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "catz");
+
+        assert!(cipher.set_filler('?').is_err());
+    }
+
+    #[test]
+    fn test_hill_length_marker_padding() {
+        let mut cipher = Hill::with_mode("gybnqkurp", TextMode::AlphaOnly).unwrap();
+        cipher.set_padding(Padding::LengthMarker);
+        assert_eq!(cipher.padding(), Padding::LengthMarker);
+
+        // A full block of padding is added when the text is already aligned
+        let encrypted = cipher.encrypt("act").unwrap();
+        assert_eq!(encrypted.len(), 6);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "actxxd");
+
+        cipher.set_unpadding(Unpadding::Exact);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "act");
+    }
+
+    #[test]
+    fn test_hill_unpadding_heuristic() {
+        let mut cipher = Hill::new("gybnqkurp").unwrap();
+        cipher.set_unpadding(Unpadding::Heuristic);
+        assert_eq!(cipher.unpadding(), Unpadding::Heuristic);
+
+        let encrypted = cipher.encrypt("Cats!").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "Cats!");
+
+        // Trailing fillers in the plaintext itself are stripped too
+        let encrypted = cipher.encrypt("box").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "bo");
+    }
+
+    #[test]
+    fn test_hill_unpadding_exact() {
+        let mut cipher = Hill::new("gybnqkurp").unwrap();
+        cipher.set_padding(Padding::LengthMarker);
+        cipher.set_unpadding(Unpadding::Exact);
+
+        for plaintext in ["box", "Boxx", "Cats!", "Attack at dawn, 0600 hrs!", "x"] {
+            let encrypted = cipher.encrypt(plaintext).unwrap();
+            assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+        }
+
+        // Ciphertext produced without a length marker is rejected
+        cipher.set_padding(Padding::Filler);
+        let encrypted = cipher.encrypt("cats").unwrap();
+        assert!(cipher.decrypt(&encrypted).is_err());
+    }
+
+    #[test]
+    fn test_hill_custom_alphabet() {
+        // 29 symbols: letters plus space, comma and period
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz ,.").unwrap();
+        let mut cipher = Hill::with_alphabet("hill", alphabet).unwrap();
+        cipher.set_unpadding(Unpadding::Heuristic);
+        assert_eq!(cipher.alphabet().len(), 29);
+
+        let plaintext = "Hello, World. 42";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert!(encrypted.contains("42"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_hill_encrypt_into_reuses_buffer() {
+        let cipher = Hill::new("gybnqkurp").unwrap();
+        let mut out = String::new();
+        for plaintext in ["act", "Attack at dawn!", "x"] {
+            out.clear();
+            cipher.encrypt_into(plaintext, &mut out).unwrap();
+            assert_eq!(out, cipher.encrypt(plaintext).unwrap());
+        }
+
+        let mut decrypted = String::from("ciphertext: ");
+        cipher.decrypt_into(&out, &mut decrypted).unwrap();
+        assert_eq!(decrypted, "ciphertext: xxx");
+        assert!(cipher.encrypt_into("123", &mut out).is_err());
+    }
+
+    #[test]
+    fn test_hill_custom_alphabet_invertibility() {
+        // det("hill") = 7*11 - 8*11 = -11: invertible mod 26, not mod 33
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456").unwrap();
+        assert!(Hill::with_alphabet("hill", alphabet).is_err());
+    }
+
+    #[test]
+    fn test_hill_large_key() {
+        // 8x8 key, far beyond what cofactor expansion over i32 could handle
+        let key = "quickbrownfoxjumpsoverthelazydogpackmyboxwithfivedozenliquorjugs";
+        let cipher = Hill::new(key).unwrap();
+        assert_eq!(cipher.key_size(), 8);
+        assert_eq!(cipher.key().determinant(26).unwrap(), 7);
+
+        let plaintext = "Sphinx of black quartz, judge my vow!";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_ne!(encrypted, plaintext);
+        assert!(cipher.decrypt(&encrypted).unwrap().starts_with(plaintext));
+    }
+
+    #[test]
+    fn test_hill_arabic() {
+        // Indices [3, 3, 2, 5] give det = 9, which is invertible mod 28
+        let mut cipher = Hill::with_alphabet("ثثتح", Alphabet::arabic()).unwrap();
+        cipher.set_padding(Padding::LengthMarker);
+        cipher.set_unpadding(Unpadding::Exact);
+
+        for plaintext in ["مرحبا بالعالم", "سلام"] {
+            let encrypted = cipher.encrypt(plaintext).unwrap();
+            assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+        }
+
+        // Indices [1, 2, 3, 4] give det = -2, which is not
+        assert!(Hill::with_alphabet("بتثج", Alphabet::arabic()).is_err());
+    }
+
     #[test]
     fn test_hill_filters_non_alpha() {
-        let cipher = Hill::new("hill").unwrap();
+        let cipher = Hill::with_mode("hill", TextMode::AlphaOnly).unwrap();
 
         let plaintext = "he11o!";
         let encrypted = cipher.encrypt(plaintext).unwrap();
@@ -238,7 +526,7 @@ mod tests {
 
     #[test]
     fn test_hill_case_handling() {
-        let cipher = Hill::new("hill").unwrap();
+        let cipher = Hill::with_mode("hill", TextMode::AlphaOnly).unwrap();
 
         // Uppercase should be converted to lowercase
         let enc1 = cipher.encrypt("HELP").unwrap();
@@ -247,6 +535,44 @@ mod tests {
         assert_eq!(enc1, enc2);
     }
 
+    #[test]
+    fn test_hill_preserve_all_case() {
+        let cipher = Hill::new("hill").unwrap();
+
+        let enc1 = cipher.encrypt("HELP").unwrap();
+        let enc2 = cipher.encrypt("help").unwrap();
+        assert_eq!(enc1, enc2.to_ascii_uppercase());
+
+        let encrypted = cipher.encrypt("HeLp").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "HeLp");
+    }
+
+    #[test]
+    fn test_hill_preserve_all_layout() {
+        let cipher = Hill::new("gybnqkurp").unwrap();
+
+        let plaintext = "Attack at dawn, 0600 hrs!";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_eq!(encrypted.len(), plaintext.len());
+        for (c, p) in encrypted.chars().zip(plaintext.chars()) {
+            assert_eq!(c.is_ascii_alphabetic(), p.is_ascii_alphabetic());
+            assert_eq!(c.is_ascii_uppercase(), p.is_ascii_uppercase());
+        }
+        assert_eq!(&encrypted[14..21], ", 0600 ");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_hill_preserve_all_appends_padding() {
+        let cipher = Hill::new("hill").unwrap();
+
+        // The padding letter lands after the trailing punctuation
+        let encrypted = cipher.encrypt("Cat!").unwrap();
+        assert_eq!(encrypted.len(), 5);
+        assert_eq!(encrypted.chars().nth(3), Some('!'));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "Cat!x");
+    }
+
     #[test]
     fn test_hill_longer_text() {
         let cipher = Hill::new("hill").unwrap();
@@ -291,19 +617,54 @@ mod tests {
         assert_eq!(cipher.name(), "hill");
     }
 
+    #[test]
+    fn test_hill_random_invertible() {
+        for size in 1..=6 {
+            let cipher = Hill::random_invertible(size).unwrap();
+            assert_eq!(cipher.key_size(), size);
+            let encrypted = cipher.encrypt("attackatdawn").unwrap();
+            assert!(
+                cipher
+                    .decrypt(&encrypted)
+                    .unwrap()
+                    .starts_with("attackatdawn")
+            );
+        }
+        assert!(Hill::random_invertible(0).is_err());
+
+        let mut generator = KeyGenerator::from_seed(5);
+        let cipher = Hill::random_invertible_with(3, &mut generator, Alphabet::arabic()).unwrap();
+        assert_eq!(cipher.alphabet().len(), 28);
+    }
+
+    #[test]
+    fn test_hill_from_matrix() {
+        let matrix = Matrix::new(2, vec![3, 3, 2, 5]).unwrap();
+        let cipher = Hill::from_matrix(&matrix, Alphabet::english()).unwrap();
+        assert_eq!(cipher.key(), &matrix);
+        assert_eq!(
+            cipher.encrypt("help").unwrap(),
+            Hill::new("ddcf").unwrap().encrypt("help").unwrap()
+        );
+
+        let singular = Matrix::new(2, vec![2, 4, 1, 2]).unwrap();
+        assert!(Hill::from_matrix(&singular, Alphabet::english()).is_err());
+    }
+
     #[test]
     fn test_matrix_determinant_2x2() {
         let matrix = Matrix::new(2, vec![7, 8, 11, 11]).unwrap();
-        let det = matrix.determinant();
-        assert_eq!(det, 7 * 11 - 8 * 11);
+        let det = matrix.determinant(26).unwrap();
+        // 7 * 11 - 8 * 11 = -11
+        assert_eq!(det, 15);
     }
 
     #[test]
     fn test_matrix_determinant_3x3() {
         let matrix = Matrix::new(3, vec![6, 24, 1, 13, 16, 10, 20, 17, 15]).unwrap();
-        let det = matrix.determinant();
+        let det = matrix.determinant(26).unwrap();
         // Verify it's invertible mod 26
-        assert_ne!(det % 26, 0);
+        assert_ne!(det, 0);
     }
 
     #[test]
@@ -312,7 +673,7 @@ mod tests {
         let inv = matrix.mod_inverse(26).unwrap();
 
         // Verify that matrix * inv ≡ I (mod 26)
-        // This is a basic sanity check
         assert_eq!(inv.size(), 2);
+        assert_eq!(matrix.multiply(&inv, 26).unwrap(), Matrix::identity(2));
     }
 }
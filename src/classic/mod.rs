@@ -1,9 +1,29 @@
+mod adfgvx;
 mod affine;
+mod bifid;
 mod caesar;
+mod columnar;
+mod enigma;
+mod four_square;
 mod hill;
 mod playfair;
+mod rail_fence;
+mod substitution;
+mod trifid;
+mod two_square;
+mod vigenere;
 
+pub use adfgvx::Adfgvx;
 pub use affine::Affine;
+pub use bifid::Bifid;
 pub use caesar::Caesar;
+pub use columnar::{ColumnFill, ColumnarTransposition};
+pub use enigma::{Enigma, EnigmaState, Reflector, Rotor};
+pub use four_square::FourSquare;
 pub use hill::Hill;
 pub use playfair::Playfair;
+pub use rail_fence::RailFence;
+pub use substitution::Substitution;
+pub use trifid::Trifid;
+pub use two_square::{TwoSquare, TwoSquareLayout};
+pub use vigenere::{Vigenere, VigenereState, VigenereVariant};
@@ -1,12 +1,17 @@
 use crate::error::PolygraphiaError;
-use crate::traits::Cipher;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::Alphabet;
+use crate::utils::KeyGenerator;
+use crate::utils::PolybiusSquare;
+use crate::utils::digraph::{DoubledLetters, PairRules, PairState};
 use crate::utils::mode::TextMode;
+use crate::utils::padding::{Padding, Unpadding};
 
 #[derive(Debug, Clone)]
 pub struct Playfair {
-    key: String,
-    matrix: [[u8; 5]; 5],
-    mode: TextMode,
+    square: PolybiusSquare,
+    alphabet: Alphabet,
+    pairs: PairRules,
 }
 
 impl Playfair {
@@ -14,16 +19,52 @@ impl Playfair {
         Self::with_mode(key, TextMode::default())
     }
 
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        Self::random_with(&mut KeyGenerator::new(), Alphabet::english_merged_ij())
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let key = generator.permutation(&alphabet);
+        Self::with_alphabet(&key, alphabet)
+    }
+
     pub fn key(&self) -> &str {
-        &self.key
+        self.square.key()
+    }
+
+    pub fn matrix(&self) -> &[Vec<u8>] {
+        self.square.matrix()
     }
 
-    pub fn matrix(&self) -> &[[u8; 5]; 5] {
-        &self.matrix
+    pub fn square(&self) -> &PolybiusSquare {
+        &self.square
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
     }
 
     pub fn mode(&self) -> TextMode {
-        self.mode
+        self.pairs.mode
+    }
+
+    pub fn filler(&self) -> char {
+        self.pairs.filler
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.pairs.padding
+    }
+
+    pub fn unpadding(&self) -> Unpadding {
+        self.pairs.unpadding
+    }
+
+    pub fn doubled_letters(&self) -> DoubledLetters {
+        self.pairs.doubled
     }
 
     pub fn set_key(&mut self, key: &str) -> Result<(), PolygraphiaError> {
@@ -32,172 +73,138 @@ impl Playfair {
                 "Key cannot be empty".to_string(),
             ));
         }
-        self.key = Self::prepare_key(key);
-        self.matrix = Self::generate_matrix(&self.key);
+        self.square = PolybiusSquare::new(key, self.alphabet.clone())?;
         Ok(())
     }
 
     pub fn set_mode(&mut self, mode: TextMode) {
-        self.mode = mode;
+        self.pairs.mode = mode;
     }
 
-    pub fn with_mode(key: &str, mode: TextMode) -> Result<Self, PolygraphiaError> {
-        if key.is_empty() {
-            return Err(PolygraphiaError::InvalidKey(
-                "Key cannot be empty".to_string(),
-            ));
-        }
-
-        let prepared_key = Self::prepare_key(key);
-        let matrix = Self::generate_matrix(&prepared_key);
+    pub fn set_filler(&mut self, filler: char) -> Result<(), PolygraphiaError> {
+        self.pairs.set_filler(filler, &self.alphabet)
+    }
 
-        Ok(Playfair {
-            key: prepared_key,
-            matrix,
-            mode,
-        })
+    pub fn set_padding(&mut self, padding: Padding) {
+        self.pairs.padding = padding;
     }
 
-    fn prepare_key(key: &str) -> String {
-        let mut unique_chars = Vec::new();
-        for c in key.chars() {
-            if !c.is_ascii_alphabetic() {
-                continue;
-            }
-            let c_lower = c.to_ascii_lowercase();
-            let c_normalized = if c_lower == 'j' { 'i' } else { c_lower };
-            if !unique_chars.contains(&c_normalized) {
-                unique_chars.push(c_normalized);
-            }
-        }
-        for c in "abcdefghiklmnopqrstuvwxyz".chars() {
-            if !unique_chars.contains(&c) {
-                unique_chars.push(c);
-            }
-        }
-        unique_chars.iter().collect()
+    pub fn set_unpadding(&mut self, unpadding: Unpadding) {
+        self.pairs.unpadding = unpadding;
     }
 
-    fn generate_matrix(key: &str) -> [[u8; 5]; 5] {
-        let mut matrix = [[0u8; 5]; 5];
-        let chars: Vec<char> = key.chars().collect();
-        for (i, &c) in chars.iter().enumerate() {
-            let row = i / 5;
-            let col = i % 5;
-            matrix[row][col] = (c as u8) - b'a';
-        }
-        matrix
+    /// With `DoubledLetters::Keep`, a pair of identical letters is enciphered
+    /// as if they were in the same row.
+    pub fn set_doubled_letters(&mut self, doubled: DoubledLetters) {
+        self.pairs.doubled = doubled;
     }
 
-    fn prepare_text(text: &str) -> String {
-        let filtered: String = text
-            .chars()
-            .filter(|c| c.is_ascii_alphabetic())
-            .map(|c| {
-                let c_lower = c.to_ascii_lowercase();
-                if c_lower == 'j' { 'i' } else { c_lower }
-            })
-            .collect();
-        let mut prepared = Vec::new();
-        let chars: Vec<char> = filtered.chars().collect();
-        let mut i = 0;
-        while i < chars.len() {
-            let first = chars[i];
-            if i == chars.len() - 1 {
-                prepared.push(first);
-                break;
-            } else if first == chars[i + 1] {
-                prepared.push(first);
-                prepared.push('x');
-            } else {
-                prepared.push(first);
-            }
-            i += 1;
-        }
-        if prepared.len() % 2 == 1 {
-            prepared.push('x');
-        }
-        prepared.iter().collect()
+    pub fn with_mode(key: &str, mode: TextMode) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet_and_mode(key, Alphabet::english_merged_ij(), mode)
     }
 
-    fn get_coordinates(&self, char_val: u8) -> Result<(usize, usize), PolygraphiaError> {
-        for row in 0..5 {
-            for col in 0..5 {
-                if self.matrix[row][col] == char_val {
-                    return Ok((row, col));
-                }
-            }
-        }
-        Err(PolygraphiaError::InvalidInput(format!(
-            "Character {} not found in matrix",
-            (char_val + b'a') as char
-        )))
+    pub fn with_alphabet(key: &str, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet_and_mode(key, alphabet, TextMode::default())
     }
 
-    fn process_pair(&self, pair: &str, encrypt: bool) -> Result<String, PolygraphiaError> {
-        let chars: Vec<char> = pair.chars().collect();
-        if chars.len() != 2 {
-            return Err(PolygraphiaError::InvalidInput(
-                "Pair must contain exactly 2 characters".to_string(),
+    pub fn with_alphabet_and_mode(
+        key: &str,
+        alphabet: Alphabet,
+        mode: TextMode,
+    ) -> Result<Self, PolygraphiaError> {
+        if key.is_empty() {
+            return Err(PolygraphiaError::InvalidKey(
+                "Key cannot be empty".to_string(),
             ));
         }
-        let char1_val = (chars[0] as u8) - b'a';
-        let char2_val = (chars[1] as u8) - b'a';
-        let (row1, col1) = self.get_coordinates(char1_val)?;
-        let (row2, col2) = self.get_coordinates(char2_val)?;
+
+        let square = PolybiusSquare::new(key, alphabet.clone())?;
+
+        Ok(Playfair {
+            square,
+            pairs: PairRules::new(&alphabet, mode, DoubledLetters::Separate),
+            alphabet,
+        })
+    }
+
+    fn process_pair(
+        &self,
+        first: char,
+        second: char,
+        encrypt: bool,
+    ) -> Result<(char, char), PolygraphiaError> {
+        let coordinates = |c: char| {
+            self.square.coordinates(c).ok_or_else(|| {
+                PolygraphiaError::InvalidInput(format!("Character {c} not found in matrix"))
+            })
+        };
+        let (row1, col1) = coordinates(first)?;
+        let (row2, col2) = coordinates(second)?;
+        let rows = self.square.rows();
+        let cols = self.square.cols();
         let (new_row1, new_col1, new_row2, new_col2) = if row1 == row2 {
-            let shift = if encrypt { 1 } else { 4 };
-            (row1, (col1 + shift) % 5, row2, (col2 + shift) % 5)
+            let shift = if encrypt { 1 } else { cols - 1 };
+            (row1, (col1 + shift) % cols, row2, (col2 + shift) % cols)
         } else if col1 == col2 {
-            let shift = if encrypt { 1 } else { 4 };
-            ((row1 + shift) % 5, col1, (row2 + shift) % 5, col2)
+            let shift = if encrypt { 1 } else { rows - 1 };
+            ((row1 + shift) % rows, col1, (row2 + shift) % rows, col2)
         } else {
             (row1, col2, row2, col1)
         };
-        let result_char1 = (self.matrix[new_row1][new_col1] + b'a') as char;
-        let result_char2 = (self.matrix[new_row2][new_col2] + b'a') as char;
-        Ok(format!("{}{}", result_char1, result_char2))
-    }
-
-    fn process_text(&self, text: &str, encrypt: bool) -> Result<String, PolygraphiaError> {
-        let prepared = Self::prepare_text(text);
-        let mut result = String::new();
-        let chars: Vec<char> = prepared.chars().collect();
-        for chunk in chars.chunks(2) {
-            let pair: String = chunk.iter().collect();
-            result.push_str(&self.process_pair(&pair, encrypt)?);
+        Ok((
+            self.square.symbol_at(new_row1, new_col1),
+            self.square.symbol_at(new_row2, new_col2),
+        ))
+    }
+
+    fn process_text(
+        &self,
+        text: &str,
+        encrypt: bool,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.pairs
+            .process_into(text, &self.alphabet, encrypt, out, |first, second| {
+                self.process_pair(first, second, encrypt)
+            })
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
         }
-        Ok(result)
+        if !text.chars().any(|c| self.alphabet.contains(c)) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one alphabetic character"
+            )));
+        }
+        Ok(())
     }
 }
 
 impl Cipher for Playfair {
     fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
-        if plaintext.is_empty() {
-            return Err(PolygraphiaError::InvalidInput(
-                "Plaintext cannot be empty".to_string(),
-            ));
-        }
-        if !plaintext.chars().any(|c| c.is_ascii_alphabetic()) {
-            return Err(PolygraphiaError::InvalidInput(
-                "Plaintext must contain at least one alphabetic character".to_string(),
-            ));
-        }
-        self.process_text(plaintext, true)
+        let mut result = String::with_capacity(plaintext.len() + 2);
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
     }
 
     fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
-        if ciphertext.is_empty() {
-            return Err(PolygraphiaError::InvalidInput(
-                "Ciphertext cannot be empty".to_string(),
-            ));
-        }
-        if !ciphertext.chars().any(|c| c.is_ascii_alphabetic()) {
-            return Err(PolygraphiaError::InvalidInput(
-                "Ciphertext must contain at least one alphabetic character".to_string(),
-            ));
-        }
-        self.process_text(ciphertext, false)
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        self.process_text(plaintext, true, out)
+    }
+
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        self.process_text(ciphertext, false, out)
     }
 
     fn name(&self) -> &str {
@@ -205,13 +212,57 @@ impl Cipher for Playfair {
     }
 }
 
-impl Drop for Playfair {
-    fn drop(&mut self) {
-        for row in &mut self.matrix {
-            for cell in row {
-                *cell = 0;
-            }
-        }
+impl StreamCipher for Playfair {
+    type State = PairState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        self.pairs
+            .process_partial(text, &self.alphabet, true, state, out, |first, second| {
+                self.process_pair(first, second, true)
+            })
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        self.pairs
+            .process_partial(text, &self.alphabet, false, state, out, |first, second| {
+                self.process_pair(first, second, false)
+            })
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Plaintext")?;
+        self.pairs
+            .process_final(text, &self.alphabet, true, state, out, |first, second| {
+                self.process_pair(first, second, true)
+            })
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Ciphertext")?;
+        self.pairs
+            .process_final(text, &self.alphabet, false, state, out, |first, second| {
+                self.process_pair(first, second, false)
+            })
     }
 }
 
@@ -219,6 +270,11 @@ impl Drop for Playfair {
 mod tests {
     use super::*;
 
+    fn prepared(cipher: &Playfair, text: &str) -> String {
+        let letters = cipher.pairs.prepare(text, &cipher.alphabet).unwrap();
+        letters.into_iter().collect()
+    }
+
     #[test]
     fn test_playfair_new() {
         let cipher = Playfair::new("secret").unwrap();
@@ -232,7 +288,8 @@ mod tests {
 
     #[test]
     fn test_playfair_prepare_key() {
-        let key = Playfair::prepare_key("secret");
+        let cipher = Playfair::new("secret").unwrap();
+        let key = cipher.key();
 
         // Should start with unique letters from "secret"
         assert!(key.starts_with("secrt"));
@@ -249,7 +306,8 @@ mod tests {
 
     #[test]
     fn test_playfair_prepare_key_with_j() {
-        let key = Playfair::prepare_key("jump");
+        let cipher = Playfair::new("jump").unwrap();
+        let key = cipher.key();
 
         // 'j' should be replaced with 'i'
         assert!(key.starts_with("iu") || key.starts_with("i")); // 'j' becomes 'i', 'u', 'm', 'p'
@@ -258,20 +316,109 @@ mod tests {
 
     #[test]
     fn test_playfair_prepare_text() {
+        let cipher = Playfair::new("secret").unwrap();
+
         // Basic text
-        assert_eq!(Playfair::prepare_text("hello"), "helxlo");
+        assert_eq!(prepared(&cipher, "hello"), "helxlo");
 
         // Duplicate letters
-        assert_eq!(Playfair::prepare_text("balloon"), "balxloxonx");
+        assert_eq!(prepared(&cipher, "balloon"), "balxloxonx");
 
         // Odd length (adds 'x')
-        assert_eq!(Playfair::prepare_text("cat"), "catx");
+        assert_eq!(prepared(&cipher, "cat"), "catx");
 
         // Replace 'j' with 'i'
-        assert_eq!(Playfair::prepare_text("jump"), "iump");
+        assert_eq!(prepared(&cipher, "jump"), "iump");
 
         // Filter non-alphabetic
-        assert_eq!(Playfair::prepare_text("he11o!"), "heox");
+        assert_eq!(prepared(&cipher, "he11o!"), "heox");
+    }
+
+    #[test]
+    fn test_playfair_custom_filler() {
+        let mut cipher = Playfair::new("secret").unwrap();
+        cipher.set_filler('Q').unwrap();
+        assert_eq!(cipher.filler(), 'q');
+        assert_eq!(prepared(&cipher, "balloon"), "balqloqonq");
+
+        // 'j' is folded into 'i' like every other letter
+        cipher.set_filler('j').unwrap();
+        assert_eq!(cipher.filler(), 'i');
+
+        assert!(cipher.set_filler('1').is_err());
+    }
+
+    #[test]
+    fn test_playfair_length_marker_padding() {
+        let mut cipher = Playfair::new("secret").unwrap();
+        cipher.set_padding(Padding::LengthMarker);
+        assert_eq!(cipher.padding(), Padding::LengthMarker);
+
+        // One letter of padding is recorded as 'b', two as 'xc'
+        assert_eq!(prepared(&cipher, "cat"), "catb");
+        assert_eq!(prepared(&cipher, "hello"), "helxloxc");
+
+        // A genuine filler between identical letters would be ambiguous
+        assert!(cipher.encrypt("exe").is_err());
+        cipher.set_filler('q').unwrap();
+        assert!(cipher.encrypt("exe").is_ok());
+    }
+
+    #[test]
+    fn test_playfair_keep_doubled_letters() {
+        let mut cipher = Playfair::with_mode("secret", TextMode::AlphaOnly).unwrap();
+        assert_eq!(cipher.doubled_letters(), DoubledLetters::Separate);
+        cipher.set_doubled_letters(DoubledLetters::Keep);
+        assert_eq!(prepared(&cipher, "balloon"), "balloonx");
+
+        let encrypted = cipher.encrypt("balloon").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "balloonx");
+    }
+
+    #[test]
+    fn test_playfair_unpadding_keep() {
+        let cipher = Playfair::with_mode("secret", TextMode::AlphaOnly).unwrap();
+        assert_eq!(cipher.unpadding(), Unpadding::Keep);
+
+        let encrypted = cipher.encrypt("balloon").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "balxloxonx");
+    }
+
+    #[test]
+    fn test_playfair_unpadding_heuristic() {
+        let mut cipher = Playfair::new("secret").unwrap();
+        cipher.set_unpadding(Unpadding::Heuristic);
+
+        for plaintext in ["balloon", "Hello, World!", "cat", "Meet me at noon"] {
+            let encrypted = cipher.encrypt(plaintext).unwrap();
+            assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+        }
+
+        // Genuine fillers that look like padding are lost
+        let encrypted = cipher.encrypt("exe").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "ee");
+    }
+
+    #[test]
+    fn test_playfair_unpadding_exact() {
+        let mut cipher = Playfair::new("secret").unwrap();
+        cipher.set_padding(Padding::LengthMarker);
+        cipher.set_unpadding(Unpadding::Exact);
+
+        for plaintext in ["balloon", "Hello, World!", "box", "boxx", "xxx", "Fox!"] {
+            let encrypted = cipher.encrypt(plaintext).unwrap();
+            assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_playfair_unpadding_exact_without_marker() {
+        let mut cipher = Playfair::with_mode("secret", TextMode::AlphaOnly).unwrap();
+        let encrypted = cipher.encrypt("hello").unwrap();
+
+        // "helxlo" ends in 'o', which is not a valid length marker
+        cipher.set_unpadding(Unpadding::Exact);
+        assert!(cipher.decrypt(&encrypted).is_err());
     }
 
     #[test]
@@ -286,7 +433,7 @@ mod tests {
 
     #[test]
     fn test_playfair_encrypt_decrypt_roundtrip() {
-        let cipher = Playfair::new("secret").unwrap();
+        let cipher = Playfair::with_mode("secret", TextMode::AlphaOnly).unwrap();
 
         let plaintext = "hello world";
         let encrypted = cipher.encrypt(plaintext).unwrap();
@@ -302,8 +449,7 @@ mod tests {
         let cipher = Playfair::new("playfairexample").unwrap();
 
         // Test characters in the same row
-        let result = cipher.process_pair("ar", true).unwrap();
-        assert_eq!(result.len(), 2);
+        assert!(cipher.process_pair('a', 'r', true).is_ok());
     }
 
     #[test]
@@ -311,8 +457,7 @@ mod tests {
         let cipher = Playfair::new("playfairexample").unwrap();
 
         // Test characters in the same column
-        let result = cipher.process_pair("mu", true).unwrap();
-        assert_eq!(result.len(), 2);
+        assert!(cipher.process_pair('m', 'u', true).is_ok());
     }
 
     #[test]
@@ -320,13 +465,12 @@ mod tests {
         let cipher = Playfair::new("playfairexample").unwrap();
 
         // Test characters forming a rectangle
-        let result = cipher.process_pair("hi", true).unwrap();
-        assert_eq!(result.len(), 2);
+        assert!(cipher.process_pair('h', 'i', true).is_ok());
     }
 
     #[test]
     fn test_playfair_case_handling() {
-        let cipher = Playfair::new("secret").unwrap();
+        let cipher = Playfair::with_mode("secret", TextMode::AlphaOnly).unwrap();
 
         // Uppercase and lowercase should produce same result
         let enc1 = cipher.encrypt("HELLO").unwrap();
@@ -336,9 +480,59 @@ mod tests {
     }
 
     #[test]
-    fn test_playfair_filters_numbers() {
+    fn test_playfair_preserve_all_case() {
         let cipher = Playfair::new("secret").unwrap();
 
+        // Letters keep their original case, fillers follow their neighbour
+        let enc1 = cipher.encrypt("HELLO").unwrap();
+        let enc2 = cipher.encrypt("hello").unwrap();
+        assert_eq!(enc1, enc2.to_ascii_uppercase());
+
+        let encrypted = cipher.encrypt("HeLp").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "HeLp");
+    }
+
+    #[test]
+    fn test_playfair_preserve_all_layout() {
+        let cipher = Playfair::new("playfair example").unwrap();
+
+        let encrypted = cipher.encrypt("Hide the gold, 42 times!").unwrap();
+        assert_eq!(encrypted, "Bmod zbx dnab, 42 vrimo!");
+        assert_eq!(
+            cipher.decrypt(&encrypted).unwrap(),
+            "Hide the gold, 42 times!"
+        );
+    }
+
+    #[test]
+    fn test_playfair_preserve_all_appends_padding() {
+        let cipher = Playfair::new("secret").unwrap();
+
+        // "cat!" needs a filler letter, which goes after the trailing '!'
+        let encrypted = cipher.encrypt("cat!").unwrap();
+        assert_eq!(encrypted.len(), 5);
+        assert_eq!(encrypted.chars().nth(3), Some('!'));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "cat!x");
+    }
+
+    #[test]
+    fn test_playfair_decrypt_adjacent_doubles() {
+        let cipher = Playfair::with_mode("playfair example", TextMode::AlphaOnly).unwrap();
+
+        // Doubled letters across pair boundaries in ciphertext must not get fillers
+        let plaintext = "hidethegoldinthetreestump";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_eq!(encrypted, "bmodzbxdnabekudmuixmmouvif");
+        assert_eq!(
+            cipher.decrypt(&encrypted).unwrap(),
+            "hidethegoldinthetrexestump"
+        );
+    }
+
+    #[test]
+    fn test_playfair_filters_numbers() {
+        let cipher = Playfair::with_mode("secret", TextMode::AlphaOnly).unwrap();
+
         let encrypted = cipher.encrypt("hello123world").unwrap();
 
         // Numbers should be filtered out
@@ -378,6 +572,16 @@ mod tests {
     fn test_playfair_cipher_trait() {
         let cipher: Box<dyn Cipher> = Box::new(Playfair::new("secret").unwrap());
         assert_eq!(cipher.name(), "playfair");
+
+        let mut out = String::from("1: ");
+        cipher.encrypt_into("Hide the gold", &mut out).unwrap();
+        assert_eq!(
+            out,
+            format!("1: {}", cipher.encrypt("Hide the gold").unwrap())
+        );
+        let mut decrypted = String::new();
+        cipher.decrypt_into(&out[3..], &mut decrypted).unwrap();
+        assert_eq!(decrypted, "Hide the goldx");
     }
 
     #[test]
@@ -397,16 +601,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_playfair_custom_alphabet_grid() {
+        // 36 symbols fill a 6x6 grid
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456789").unwrap();
+        let cipher = Playfair::with_alphabet("secret 2024", alphabet).unwrap();
+        assert_eq!(cipher.matrix().len(), 6);
+        assert_eq!(cipher.matrix()[0].len(), 6);
+        assert!(cipher.key().starts_with("secrt204"));
+
+        let mut cipher = cipher;
+        cipher.set_unpadding(Unpadding::Heuristic);
+        let plaintext = "Meet at 10am, Jetty 7";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_playfair_rectangular_grid() {
+        // 30 symbols make a 5x6 grid
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz.,?!").unwrap();
+        let cipher = Playfair::with_alphabet("keyword", alphabet).unwrap();
+        assert_eq!(cipher.matrix().len(), 5);
+        assert_eq!(cipher.matrix()[0].len(), 6);
+
+        // Same-row pairs wrap around the six columns
+        let encrypted = cipher.encrypt("Who, me?!").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "Who, me?!");
+    }
+
+    #[test]
+    fn test_playfair_prime_alphabet_rejected() {
+        // 29 symbols cannot form a grid
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz.,?").unwrap();
+        assert!(Playfair::with_alphabet("keyword", alphabet).is_err());
+    }
+
+    #[test]
+    fn test_playfair_arabic() {
+        // 28 letters fill a 4x7 grid
+        let mut cipher = Playfair::with_alphabet("مفتاح", Alphabet::arabic()).unwrap();
+        assert_eq!(cipher.matrix().len(), 4);
+        assert_eq!(cipher.matrix()[0].len(), 7);
+        assert!(cipher.key().starts_with("مفتاح"));
+
+        cipher.set_unpadding(Unpadding::Heuristic);
+        for plaintext in ["مرحبا بالعالم", "السلام عليكم"] {
+            let encrypted = cipher.encrypt(plaintext).unwrap();
+            assert_ne!(encrypted, plaintext);
+            assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_playfair_random() {
+        let cipher = Playfair::random().unwrap();
+        let mut letters: Vec<u8> = cipher.matrix().concat();
+        letters.sort_unstable();
+        assert_eq!(letters, (0..25).collect::<Vec<u8>>());
+
+        let a = Playfair::random_with(
+            &mut KeyGenerator::from_seed(9),
+            Alphabet::english_merged_ij(),
+        );
+        let b = Playfair::random_with(
+            &mut KeyGenerator::from_seed(9),
+            Alphabet::english_merged_ij(),
+        );
+        assert_eq!(a.unwrap().key(), b.unwrap().key());
+    }
+
     #[test]
     fn test_playfair_get_coordinates() {
         let cipher = Playfair::new("abcdefghiklmnopqrstuvwxyz").unwrap();
 
         // 'a' should be at (0, 0)
-        let (row, col) = cipher.get_coordinates(0).unwrap();
+        let (row, col) = cipher.square().position(0);
         assert_eq!((row, col), (0, 0));
 
         // 'e' should be at (0, 4)
-        let (row, col) = cipher.get_coordinates(4).unwrap();
+        let (row, col) = cipher.square().position(4);
         assert_eq!((row, col), (0, 4));
     }
 }
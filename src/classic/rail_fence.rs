@@ -0,0 +1,235 @@
+use rand::Rng;
+
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::text;
+use crate::utils::{Alphabet, KeyGenerator, TextMode};
+
+/// Writes the letters in a zigzag across `rails` rows and reads the rows off
+/// top to bottom. Non-letters keep their places under `TextMode::PreserveAll`.
+#[derive(Debug, Clone)]
+pub struct RailFence {
+    rails: usize,
+    offset: usize,
+    alphabet: Alphabet,
+    mode: TextMode,
+}
+
+impl RailFence {
+    pub fn new(rails: usize) -> Result<Self, PolygraphiaError> {
+        Self::with_offset(rails, 0)
+    }
+
+    /// Starts the zigzag `offset` steps into its cycle, as if that many letters
+    /// had already been written.
+    pub fn with_offset(rails: usize, offset: usize) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(rails, offset, Alphabet::default())
+    }
+
+    pub fn with_alphabet(
+        rails: usize,
+        offset: usize,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        Self::validate_rails(rails)?;
+        Ok(RailFence {
+            rails,
+            offset: offset % Self::cycle(rails),
+            alphabet,
+            mode: TextMode::default(),
+        })
+    }
+
+    pub fn random(max_rails: usize) -> Result<Self, PolygraphiaError> {
+        Self::random_with(max_rails, &mut KeyGenerator::new(), Alphabet::default())
+    }
+
+    /// Between 2 and `max_rails` rails, starting anywhere in the zigzag.
+    pub fn random_with(
+        max_rails: usize,
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        Self::validate_rails(max_rails)?;
+        let rails = generator.rng().random_range(2..=max_rails);
+        let offset = generator.rng().random_range(0..Self::cycle(rails));
+        Self::with_alphabet(rails, offset, alphabet)
+    }
+
+    pub fn rails(&self) -> usize {
+        self.rails
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    pub fn set_rails(&mut self, rails: usize) -> Result<(), PolygraphiaError> {
+        Self::validate_rails(rails)?;
+        self.rails = rails;
+        self.offset %= Self::cycle(rails);
+        Ok(())
+    }
+
+    pub fn set_offset(&mut self, offset: usize) {
+        self.offset = offset % Self::cycle(self.rails);
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    fn validate_rails(rails: usize) -> Result<(), PolygraphiaError> {
+        if rails < 2 {
+            return Err(PolygraphiaError::InvalidKey(
+                "Rail fence needs at least 2 rails".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn cycle(rails: usize) -> usize {
+        2 * (rails - 1)
+    }
+
+    /// Source position of each ciphertext letter: positions grouped by rail.
+    fn order(&self, length: usize) -> Vec<usize> {
+        let cycle = Self::cycle(self.rails);
+        let rail = |pos: usize| {
+            let step = (pos + self.offset) % cycle;
+            if step < self.rails {
+                step
+            } else {
+                cycle - step
+            }
+        };
+        let mut order: Vec<usize> = (0..length).collect();
+        order.sort_by_key(|&pos| rail(pos));
+        order
+    }
+
+    fn process_text(&self, text: &str, encrypt: bool) -> String {
+        let letters: Vec<char> = text
+            .chars()
+            .filter(|&c| self.alphabet.contains(c))
+            .collect();
+        let letters = text::transpose(&letters, &self.order(letters.len()), encrypt);
+        match self.mode {
+            TextMode::PreserveAll => text::restore_layout(text, &letters, &self.alphabet),
+            TextMode::AlphaOnly => letters.into_iter().collect(),
+        }
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
+        }
+        if !text.chars().any(|c| self.alphabet.contains(c)) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one alphabetic character"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for RailFence {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        Ok(self.process_text(plaintext, true))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        Ok(self.process_text(ciphertext, false))
+    }
+
+    fn name(&self) -> &str {
+        "rail-fence"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rail_fence_encrypt() {
+        let cipher = RailFence::new(3).unwrap();
+        assert_eq!(
+            cipher.encrypt("WEAREDISCOVEREDFLEEATONCE").unwrap(),
+            "WECRLTEERDSOEEFEAOCAIVDEN"
+        );
+        assert_eq!(
+            cipher.decrypt("WECRLTEERDSOEEFEAOCAIVDEN").unwrap(),
+            "WEAREDISCOVEREDFLEEATONCE"
+        );
+    }
+
+    #[test]
+    fn test_rail_fence_offset() {
+        let cipher = RailFence::with_offset(3, 1).unwrap();
+        assert_eq!(cipher.encrypt("abcdef").unwrap(), "dacebf");
+        assert_eq!(cipher.decrypt("dacebf").unwrap(), "abcdef");
+
+        // Offsets wrap at the zigzag cycle
+        assert_eq!(RailFence::with_offset(3, 5).unwrap().offset(), 1);
+    }
+
+    #[test]
+    fn test_rail_fence_preserves_layout() {
+        let mut cipher = RailFence::new(2).unwrap();
+        assert_eq!(cipher.encrypt("Hello, World!").unwrap(), "Hlool, elWrd!");
+        assert_eq!(cipher.decrypt("Hlool, elWrd!").unwrap(), "Hello, World!");
+
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert_eq!(cipher.encrypt("Hello, World!").unwrap(), "HloolelWrd");
+    }
+
+    #[test]
+    fn test_rail_fence_roundtrip() {
+        let text = "The quick brown fox jumps over the lazy dog.";
+        for rails in 2..12 {
+            for offset in 0..2 * (rails - 1) {
+                let cipher = RailFence::with_offset(rails, offset).unwrap();
+                assert_eq!(
+                    cipher.decrypt(&cipher.encrypt(text).unwrap()).unwrap(),
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_rail_fence_random() {
+        let mut generator = KeyGenerator::from_seed(4);
+        for _ in 0..20 {
+            let cipher = RailFence::random_with(5, &mut generator, Alphabet::english()).unwrap();
+            assert!((2..=5).contains(&cipher.rails()));
+            let ciphertext = cipher.encrypt("Attack at dawn").unwrap();
+            assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Attack at dawn");
+        }
+        assert_eq!(RailFence::random(2).unwrap().rails(), 2);
+        assert!(RailFence::random(1).is_err());
+    }
+
+    #[test]
+    fn test_rail_fence_invalid() {
+        assert!(RailFence::new(1).is_err());
+        let mut cipher = RailFence::new(3).unwrap();
+        assert!(cipher.set_rails(0).is_err());
+        assert!(cipher.encrypt("").is_err());
+        assert!(cipher.encrypt("123").is_err());
+        assert_eq!(cipher.name(), "rail-fence");
+    }
+}
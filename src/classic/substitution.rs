@@ -0,0 +1,369 @@
+use crate::error::PolygraphiaError;
+use crate::stream::BlockState;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::{Alphabet, KeyGenerator, TextMode, text};
+
+/// Monoalphabetic substitution: every symbol of the alphabet is replaced by
+/// the symbol at the same position in the key.
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    key: String,
+    forward: Vec<u8>,
+    inverse: Vec<u8>,
+    alphabet: Alphabet,
+    mode: TextMode,
+}
+
+impl Substitution {
+    /// `key` must list every letter of the English alphabet exactly once,
+    /// e.g. `"qwertyuiopasdfghjklzxcvbnm"`.
+    pub fn new(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(key, Alphabet::default())
+    }
+
+    pub fn with_alphabet(key: &str, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet_and_mode(key, alphabet, TextMode::default())
+    }
+
+    pub fn with_mode(key: &str, mode: TextMode) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet_and_mode(key, Alphabet::default(), mode)
+    }
+
+    pub fn with_alphabet_and_mode(
+        key: &str,
+        alphabet: Alphabet,
+        mode: TextMode,
+    ) -> Result<Self, PolygraphiaError> {
+        let forward = Self::parse_key(key, &alphabet)?;
+        Ok(Self::build(forward, alphabet, mode))
+    }
+
+    /// Keyword-mixed alphabet: the keyword's letters without repeats, then the
+    /// rest of the alphabet in order, as for a Playfair square.
+    pub fn from_keyword(keyword: &str) -> Result<Self, PolygraphiaError> {
+        Self::from_keyword_with_alphabet(keyword, Alphabet::default())
+    }
+
+    pub fn from_keyword_with_alphabet(
+        keyword: &str,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        if !keyword.chars().any(|c| alphabet.contains(c)) {
+            return Err(PolygraphiaError::InvalidKey(
+                "Keyword must contain at least one letter of the alphabet".to_string(),
+            ));
+        }
+        let forward = alphabet
+            .keyword_order(keyword)
+            .into_iter()
+            .map(|idx| idx as u8)
+            .collect();
+        Ok(Self::build(forward, alphabet, TextMode::default()))
+    }
+
+    /// The alphabet reversed: a <-> z, b <-> y, ...
+    pub fn atbash() -> Self {
+        Self::atbash_with(Alphabet::default())
+    }
+
+    pub fn atbash_with(alphabet: Alphabet) -> Self {
+        let forward = (0..alphabet.len()).rev().map(|idx| idx as u8).collect();
+        Self::build(forward, alphabet, TextMode::default())
+    }
+
+    /// Caesar shift of 13 over the English alphabet; its own inverse.
+    pub fn rot13() -> Self {
+        let forward = (0..26).map(|idx| (idx + 13) % 26).collect();
+        Self::build(forward, Alphabet::english(), TextMode::default())
+    }
+
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        Self::random_with(&mut KeyGenerator::new(), Alphabet::default())
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let key = generator.permutation(&alphabet);
+        Self::with_alphabet(&key, alphabet)
+    }
+
+    /// The cipher alphabet: the substitute for each plaintext symbol in order.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    pub fn set_key(&mut self, key: &str) -> Result<(), PolygraphiaError> {
+        let forward = Self::parse_key(key, &self.alphabet)?;
+        *self = Self::build(forward, self.alphabet.clone(), self.mode);
+        Ok(())
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    fn parse_key(key: &str, alphabet: &Alphabet) -> Result<Vec<u8>, PolygraphiaError> {
+        let mut seen = vec![false; alphabet.len()];
+        let mut forward = Vec::with_capacity(alphabet.len());
+        for c in key.chars() {
+            let idx = alphabet.index_of(c).ok_or_else(|| {
+                PolygraphiaError::InvalidKey(format!("Key symbol '{c}' is not in the alphabet"))
+            })?;
+            if seen[idx] {
+                return Err(PolygraphiaError::InvalidKey(format!(
+                    "Key symbol '{c}' appears more than once"
+                )));
+            }
+            seen[idx] = true;
+            forward.push(idx as u8);
+        }
+        if forward.len() != alphabet.len() {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Key must contain all {} symbols of the alphabet, got {}",
+                alphabet.len(),
+                forward.len()
+            )));
+        }
+        Ok(forward)
+    }
+
+    fn build(forward: Vec<u8>, alphabet: Alphabet, mode: TextMode) -> Self {
+        let mut inverse = vec![0u8; forward.len()];
+        for (idx, &target) in forward.iter().enumerate() {
+            inverse[target as usize] = idx as u8;
+        }
+        Substitution {
+            key: forward
+                .iter()
+                .map(|&idx| alphabet.symbol(idx as usize))
+                .collect(),
+            forward,
+            inverse,
+            alphabet,
+            mode,
+        }
+    }
+
+    /// The character `c` becomes, or `None` if it is dropped.
+    fn map_char(&self, c: char, encrypt: bool) -> Option<char> {
+        let table = if encrypt {
+            &self.forward
+        } else {
+            &self.inverse
+        };
+        match self.alphabet.index_of(c) {
+            Some(idx) => Some(self.alphabet.symbol_like(table[idx] as usize, c)),
+            None if self.mode == TextMode::PreserveAll => Some(c),
+            None => None,
+        }
+    }
+
+    fn process_text(&self, text: &str, encrypt: bool, out: &mut String) {
+        out.extend(text.chars().filter_map(|c| self.map_char(c, encrypt)));
+    }
+}
+
+impl Cipher for Substitution {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(plaintext.len());
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        if plaintext.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(
+                "Empty plaintext".to_string(),
+            ));
+        }
+        let start = out.len();
+        self.process_text(plaintext, true, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
+            return Err(PolygraphiaError::InvalidInput(
+                "Plaintext must contain at least one alphabetic character".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        if ciphertext.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(
+                "Empty ciphertext".to_string(),
+            ));
+        }
+        let start = out.len();
+        self.process_text(ciphertext, false, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
+            return Err(PolygraphiaError::InvalidInput(
+                "Ciphertext must contain at least one alphabetic character".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn encrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Plaintext", || {
+            |c| self.map_char(c, true)
+        })
+    }
+
+    fn decrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Ciphertext", || {
+            |c| self.map_char(c, false)
+        })
+    }
+
+    fn name(&self) -> &str {
+        "substitution"
+    }
+}
+
+impl StreamCipher for Substitution {
+    type State = BlockState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], true, out);
+        Ok(cut)
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], false, out);
+        Ok(cut)
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.encrypt_into(text, out)
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        _state: &mut BlockState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.decrypt_into(text, out)
+    }
+}
+
+impl Drop for Substitution {
+    fn drop(&mut self) {
+        self.forward.fill(0);
+        self.inverse.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitution_new() {
+        let cipher = Substitution::new("QWERTYUIOPASDFGHJKLZXCVBNM").unwrap();
+        assert_eq!(cipher.key(), "qwertyuiopasdfghjklzxcvbnm");
+        assert_eq!(cipher.encrypt("Hello, World!").unwrap(), "Itssg, Vgksr!");
+        assert_eq!(cipher.decrypt("Itssg, Vgksr!").unwrap(), "Hello, World!");
+    }
+
+    #[test]
+    fn test_substitution_invalid_key() {
+        assert!(Substitution::new("abc").is_err());
+        assert!(Substitution::new("aacdefghijklmnopqrstuvwxyz").is_err());
+        assert!(Substitution::new("abcdefghijklmnopqrstuvwxy1").is_err());
+        assert!(Substitution::from_keyword("123").is_err());
+    }
+
+    #[test]
+    fn test_substitution_keyword() {
+        let cipher = Substitution::from_keyword("Zebras").unwrap();
+        assert_eq!(cipher.key(), "zebrascdfghijklmnopqtuvwxy");
+        assert_eq!(
+            cipher.encrypt("flee at once. we are discovered!").unwrap(),
+            "siaa zq lkba. va zoa rfpbluaoar!"
+        );
+    }
+
+    #[test]
+    fn test_substitution_presets() {
+        let atbash = Substitution::atbash();
+        assert_eq!(atbash.encrypt("Wizard").unwrap(), "Draziw");
+        assert_eq!(atbash.decrypt("Draziw").unwrap(), "Wizard");
+
+        let rot13 = Substitution::rot13();
+        assert_eq!(rot13.encrypt("Hello, World!").unwrap(), "Uryyb, Jbeyq!");
+        assert_eq!(rot13.encrypt("Uryyb, Jbeyq!").unwrap(), "Hello, World!");
+    }
+
+    #[test]
+    fn test_substitution_alpha_only() {
+        let mut cipher =
+            Substitution::with_mode("zyxwvutsrqponmlkjihgfedcba", TextMode::AlphaOnly).unwrap();
+        assert_eq!(cipher.encrypt("Hi there 42!").unwrap(), "Srgsviv");
+        assert!(cipher.encrypt("42!").is_err());
+        assert!(cipher.encrypt("").is_err());
+
+        cipher.set_mode(TextMode::PreserveAll);
+        assert_eq!(cipher.encrypt("42!").unwrap(), "42!");
+    }
+
+    #[test]
+    fn test_substitution_set_key() {
+        let mut cipher = Substitution::atbash();
+        cipher.set_key("bcdefghijklmnopqrstuvwxyza").unwrap();
+        assert_eq!(cipher.encrypt("abc").unwrap(), "bcd");
+        assert!(cipher.set_key("abc").is_err());
+        assert_eq!(cipher.encrypt("abc").unwrap(), "bcd");
+    }
+
+    #[test]
+    fn test_substitution_arabic() {
+        let cipher = Substitution::atbash_with(Alphabet::arabic());
+        let ciphertext = cipher.encrypt("سلام عليكم").unwrap();
+        assert_ne!(ciphertext, "سلام عليكم");
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "سلام عليكم");
+    }
+
+    #[test]
+    fn test_substitution_random() {
+        let a = Substitution::random_with(&mut KeyGenerator::from_seed(5), Alphabet::english())
+            .unwrap();
+        let b = Substitution::random_with(&mut KeyGenerator::from_seed(5), Alphabet::english())
+            .unwrap();
+        assert_eq!(a.key(), b.key());
+        assert_eq!(a.decrypt(&a.encrypt("Hello").unwrap()).unwrap(), "Hello");
+        assert_eq!(Substitution::random().unwrap().name(), "substitution");
+    }
+}
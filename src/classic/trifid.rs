@@ -0,0 +1,230 @@
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::text;
+use crate::utils::{Alphabet, KeyGenerator, PolybiusSquare, TextMode};
+
+const TRIFID_SYMBOLS: &str = "abcdefghijklmnopqrstuvwxyz.";
+const DEFAULT_PERIOD: usize = 5;
+
+/// Delastelle's Trifid cipher: Bifid with a 3x3x3 cube of 27 symbols, so each
+/// letter splits into a layer, row and column number.
+///
+/// The default alphabet is a-z plus `.`, which therefore counts as a letter.
+#[derive(Debug, Clone)]
+pub struct Trifid {
+    /// The cube, stored as nine rows of three: layer `l` is rows `3l..3l + 3`.
+    cube: PolybiusSquare,
+    period: usize,
+    mode: TextMode,
+}
+
+impl Trifid {
+    pub fn new(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_period(key, DEFAULT_PERIOD)
+    }
+
+    pub fn with_period(key: &str, period: usize) -> Result<Self, PolygraphiaError> {
+        let alphabet = Alphabet::new(TRIFID_SYMBOLS).expect("Trifid alphabet is valid");
+        Self::with_alphabet(key, period, alphabet)
+    }
+
+    /// `alphabet` must have exactly 27 symbols.
+    pub fn with_alphabet(
+        key: &str,
+        period: usize,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        if alphabet.len() != 27 {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Trifid needs 27 symbols, got {}",
+                alphabet.len()
+            )));
+        }
+        Self::validate_period(period)?;
+        Ok(Trifid {
+            cube: PolybiusSquare::with_shape(key, alphabet, 9, 3)?,
+            period,
+            mode: TextMode::default(),
+        })
+    }
+
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        let alphabet = Alphabet::new(TRIFID_SYMBOLS).expect("Trifid alphabet is valid");
+        Self::random_with(&mut KeyGenerator::new(), DEFAULT_PERIOD, alphabet)
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        period: usize,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let key = generator.permutation(&alphabet);
+        Self::with_alphabet(&key, period, alphabet)
+    }
+
+    pub fn key(&self) -> &str {
+        self.cube.key()
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        self.cube.alphabet()
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    pub fn set_period(&mut self, period: usize) -> Result<(), PolygraphiaError> {
+        Self::validate_period(period)?;
+        self.period = period;
+        Ok(())
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    fn validate_period(period: usize) -> Result<(), PolygraphiaError> {
+        if period == 0 {
+            return Err(PolygraphiaError::InvalidKey(
+                "Period must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Layer, row and column of a symbol.
+    fn coordinates(&self, c: char) -> Option<[usize; 3]> {
+        self.cube
+            .coordinates(c)
+            .map(|(row, col)| [row / 3, row % 3, col])
+    }
+
+    fn process_block(&self, block: &[char], encrypt: bool) -> Vec<char> {
+        let coordinates: Vec<[usize; 3]> =
+            block.iter().filter_map(|&c| self.coordinates(c)).collect();
+        let len = coordinates.len();
+        let output: Vec<[usize; 3]> = if encrypt {
+            let line: Vec<usize> = (0..3)
+                .flat_map(|axis| coordinates.iter().map(move |coords| coords[axis]))
+                .collect();
+            line.chunks(3)
+                .map(|triple| [triple[0], triple[1], triple[2]])
+                .collect()
+        } else {
+            let line: Vec<usize> = coordinates.concat();
+            (0..len)
+                .map(|i| [line[i], line[len + i], line[2 * len + i]])
+                .collect()
+        };
+        output
+            .into_iter()
+            .zip(block)
+            .map(|([layer, row, col], &original)| {
+                let idx = self.cube.index_at(layer * 3 + row, col);
+                self.cube.alphabet().symbol_like(idx, original)
+            })
+            .collect()
+    }
+
+    fn process_text(&self, text: &str, encrypt: bool) -> String {
+        let alphabet = self.cube.alphabet();
+        let letters: Vec<char> = text.chars().filter(|&c| alphabet.contains(c)).collect();
+        let letters: Vec<char> = letters
+            .chunks(self.period)
+            .flat_map(|block| self.process_block(block, encrypt))
+            .collect();
+        match self.mode {
+            TextMode::PreserveAll => text::restore_layout(text, &letters, alphabet),
+            TextMode::AlphaOnly => letters.into_iter().collect(),
+        }
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
+        }
+        if !text.chars().any(|c| self.cube.alphabet().contains(c)) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one alphabetic character"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for Trifid {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        Ok(self.process_text(plaintext, true))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        Ok(self.process_text(ciphertext, false))
+    }
+
+    fn name(&self) -> &str {
+        "trifid"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "epsducvwym.zlkxnbtfgorijhaq";
+
+    #[test]
+    fn test_trifid_known_answer() {
+        let mut cipher = Trifid::new(KEY).unwrap();
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert_eq!(
+            cipher
+                .encrypt("defend the east wall of the castle.")
+                .unwrap(),
+            "suefecphsegyyjiximfofocejlbsp"
+        );
+        assert_eq!(
+            cipher.decrypt("SUEFECPHSEGYYJIXIMFOFOCEJLBSP").unwrap(),
+            "DEFENDTHEEASTWALLOFTHECASTLE."
+        );
+    }
+
+    #[test]
+    fn test_trifid_preserve_all() {
+        let cipher = Trifid::with_period(KEY, 7).unwrap();
+        let plaintext = "Attack at dawn, 6am!";
+        let ciphertext = cipher.encrypt(plaintext).unwrap();
+        assert_eq!(&ciphertext[6..7], " ");
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_trifid_random() {
+        let cipher = Trifid::random().unwrap();
+        assert_eq!(cipher.key().chars().count(), 27);
+        assert_eq!(cipher.period(), DEFAULT_PERIOD);
+        let ciphertext = cipher.encrypt("Attack at dawn.").unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Attack at dawn.");
+
+        let mut generator = KeyGenerator::from_seed(6);
+        assert!(Trifid::random_with(&mut generator, 0, cipher.alphabet().clone()).is_err());
+    }
+
+    #[test]
+    fn test_trifid_invalid() {
+        assert!(Trifid::with_period(KEY, 0).is_err());
+        assert!(Trifid::with_alphabet(KEY, 5, Alphabet::english()).is_err());
+        let mut cipher = Trifid::new(KEY).unwrap();
+        assert!(cipher.set_period(0).is_err());
+        assert!(cipher.encrypt("123").is_err());
+        assert_eq!(cipher.name(), "trifid");
+    }
+}
@@ -0,0 +1,360 @@
+use crate::error::PolygraphiaError;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::digraph::{DoubledLetters, PairRules, PairState};
+use crate::utils::padding::{Padding, Unpadding};
+use crate::utils::{Alphabet, KeyGenerator, PolybiusSquare, TextMode};
+
+/// How the two squares of a Two-square cipher are placed.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoSquareLayout {
+    /// The first square on the left, the second on the right.
+    #[default]
+    Horizontal,
+    /// The first square above the second.
+    Vertical,
+}
+
+/// Two-square (double Playfair): the first letter of each pair is found in
+/// the first square and the second letter in the second, and each is replaced
+/// by the letter of its own square in line with the other one: in the other's
+/// row when the squares sit side by side, in its column when they are stacked.
+/// Pairs in the same row (horizontal) or column (vertical) are left as they are.
+///
+/// Every step is its own inverse, so encryption and decryption coincide apart
+/// from padding.
+#[derive(Debug, Clone)]
+pub struct TwoSquare {
+    first: PolybiusSquare,
+    second: PolybiusSquare,
+    layout: TwoSquareLayout,
+    pairs: PairRules,
+}
+
+impl TwoSquare {
+    pub fn new(first_key: &str, second_key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(
+            first_key,
+            second_key,
+            TwoSquareLayout::default(),
+            Alphabet::english_merged_ij(),
+        )
+    }
+
+    pub fn vertical(first_key: &str, second_key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet(
+            first_key,
+            second_key,
+            TwoSquareLayout::Vertical,
+            Alphabet::english_merged_ij(),
+        )
+    }
+
+    pub fn with_alphabet(
+        first_key: &str,
+        second_key: &str,
+        layout: TwoSquareLayout,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        if first_key.is_empty() || second_key.is_empty() {
+            return Err(PolygraphiaError::InvalidKey(
+                "Keys cannot be empty".to_string(),
+            ));
+        }
+        Ok(TwoSquare {
+            first: PolybiusSquare::new(first_key, alphabet.clone())?,
+            second: PolybiusSquare::new(second_key, alphabet.clone())?,
+            layout,
+            pairs: PairRules::new(&alphabet, TextMode::default(), DoubledLetters::Keep),
+        })
+    }
+
+    pub fn random() -> Result<Self, PolygraphiaError> {
+        Self::random_with(
+            &mut KeyGenerator::new(),
+            TwoSquareLayout::default(),
+            Alphabet::english_merged_ij(),
+        )
+    }
+
+    pub fn random_with(
+        generator: &mut KeyGenerator,
+        layout: TwoSquareLayout,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let first_key = generator.permutation(&alphabet);
+        let second_key = generator.permutation(&alphabet);
+        Self::with_alphabet(&first_key, &second_key, layout, alphabet)
+    }
+
+    pub fn first_key(&self) -> &str {
+        self.first.key()
+    }
+
+    pub fn second_key(&self) -> &str {
+        self.second.key()
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        self.first.alphabet()
+    }
+
+    pub fn layout(&self) -> TwoSquareLayout {
+        self.layout
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.pairs.mode
+    }
+
+    pub fn filler(&self) -> char {
+        self.pairs.filler
+    }
+
+    pub fn padding(&self) -> Padding {
+        self.pairs.padding
+    }
+
+    pub fn unpadding(&self) -> Unpadding {
+        self.pairs.unpadding
+    }
+
+    pub fn doubled_letters(&self) -> DoubledLetters {
+        self.pairs.doubled
+    }
+
+    pub fn set_layout(&mut self, layout: TwoSquareLayout) {
+        self.layout = layout;
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.pairs.mode = mode;
+    }
+
+    pub fn set_filler(&mut self, filler: char) -> Result<(), PolygraphiaError> {
+        self.pairs.set_filler(filler, self.first.alphabet())
+    }
+
+    pub fn set_padding(&mut self, padding: Padding) {
+        self.pairs.padding = padding;
+    }
+
+    pub fn set_unpadding(&mut self, unpadding: Unpadding) {
+        self.pairs.unpadding = unpadding;
+    }
+
+    pub fn set_doubled_letters(&mut self, doubled: DoubledLetters) {
+        self.pairs.doubled = doubled;
+    }
+
+    fn process_pair(&self, first: char, second: char) -> (char, char) {
+        let (row1, col1) = self.first.coordinates(first).expect("letter in alphabet");
+        let (row2, col2) = self.second.coordinates(second).expect("letter in alphabet");
+        match self.layout {
+            TwoSquareLayout::Horizontal if row1 != row2 => (
+                self.first.symbol_at(row2, col1),
+                self.second.symbol_at(row1, col2),
+            ),
+            TwoSquareLayout::Vertical if col1 != col2 => (
+                self.first.symbol_at(row1, col2),
+                self.second.symbol_at(row2, col1),
+            ),
+            _ => (
+                self.first.symbol_at(row1, col1),
+                self.second.symbol_at(row2, col2),
+            ),
+        }
+    }
+
+    fn process_text(
+        &self,
+        text: &str,
+        encrypt: bool,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.pairs.process_into(
+            text,
+            self.first.alphabet(),
+            encrypt,
+            out,
+            |first, second| Ok(self.process_pair(first, second)),
+        )
+    }
+
+    fn validate_input(&self, text: &str, what: &str) -> Result<(), PolygraphiaError> {
+        if text.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} cannot be empty"
+            )));
+        }
+        if !text.chars().any(|c| self.first.alphabet().contains(c)) {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "{what} must contain at least one alphabetic character"
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Cipher for TwoSquare {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(plaintext.len() + 2);
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        self.validate_input(plaintext, "Plaintext")?;
+        self.process_text(plaintext, true, out)
+    }
+
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        self.validate_input(ciphertext, "Ciphertext")?;
+        self.process_text(ciphertext, false, out)
+    }
+
+    fn name(&self) -> &str {
+        "two-square"
+    }
+}
+
+impl StreamCipher for TwoSquare {
+    type State = PairState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        self.pairs.process_partial(
+            text,
+            self.first.alphabet(),
+            true,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second)),
+        )
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        self.pairs.process_partial(
+            text,
+            self.first.alphabet(),
+            false,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second)),
+        )
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Plaintext")?;
+        self.pairs.process_final(
+            text,
+            self.first.alphabet(),
+            true,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second)),
+        )
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        state: &mut PairState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        self.validate_input(text, "Ciphertext")?;
+        self.pairs.process_final(
+            text,
+            self.first.alphabet(),
+            false,
+            state,
+            out,
+            |first, second| Ok(self.process_pair(first, second)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 25 letters without 'q'
+    const ALPHABET: &str = "abcdefghijklmnoprstuvwxyz";
+
+    fn cipher(layout: TwoSquareLayout) -> TwoSquare {
+        let alphabet = Alphabet::new(ALPHABET).unwrap();
+        let mut cipher = TwoSquare::with_alphabet("example", "keyword", layout, alphabet).unwrap();
+        cipher.set_mode(TextMode::AlphaOnly);
+        cipher
+    }
+
+    #[test]
+    fn test_two_square_horizontal() {
+        let cipher = cipher(TwoSquareLayout::Horizontal);
+        // "he" trade rows, "lp" trade rows, "me" share a row and stay put
+        assert_eq!(cipher.encrypt("Help me").unwrap(), "xgnbme");
+        assert_eq!(cipher.decrypt("XGNBME").unwrap(), "helpme");
+
+        let plaintext = "help me obi wan kenobi";
+        let ciphertext = cipher.encrypt(plaintext).unwrap();
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "helpmeobiwankenobi");
+    }
+
+    #[test]
+    fn test_two_square_vertical() {
+        let cipher = cipher(TwoSquareLayout::Vertical);
+        // "he" shares a column, so it shows through unchanged
+        assert_eq!(
+            cipher.encrypt("help me obi wan kenobi").unwrap(),
+            "hedlxwsdjyanhotkdg"
+        );
+        assert_eq!(
+            cipher.decrypt("HEDLXWSDJYANHOTKDG").unwrap(),
+            "helpmeobiwankenobi"
+        );
+    }
+
+    #[test]
+    fn test_two_square_pairs() {
+        let mut cipher = TwoSquare::vertical("zebras", "dingo").unwrap();
+        assert_eq!(cipher.layout(), TwoSquareLayout::Vertical);
+        cipher.set_unpadding(Unpadding::Heuristic);
+        for plaintext in ["Hello, World!", "Meet me at noon", "Jolly"] {
+            let ciphertext = cipher.encrypt(plaintext).unwrap();
+            assert_eq!(
+                cipher.decrypt(&ciphertext).unwrap(),
+                plaintext.replace('J', "I")
+            );
+        }
+
+        cipher.set_layout(TwoSquareLayout::Horizontal);
+        cipher.set_doubled_letters(DoubledLetters::Separate);
+        cipher.set_mode(TextMode::AlphaOnly);
+        let ciphertext = cipher.encrypt("jolly").unwrap();
+        assert_eq!(ciphertext.len(), 6);
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "iolly");
+
+        assert!(TwoSquare::new("key", "").is_err());
+        assert!(cipher.decrypt("!!").is_err());
+        assert_eq!(cipher.name(), "two-square");
+    }
+}
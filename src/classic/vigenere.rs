@@ -0,0 +1,585 @@
+use std::collections::VecDeque;
+
+use crate::error::PolygraphiaError;
+use crate::stream::BlockState;
+use crate::traits::{Cipher, StreamCipher};
+use crate::utils::{Alphabet, KeyGenerator, TextMode, text};
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VigenereVariant {
+    #[default]
+    Vigenere,
+    Beaufort,
+    VariantBeaufort,
+    Gronsfeld,
+    Autokey,
+}
+
+#[derive(Debug, Clone)]
+pub struct Vigenere {
+    key: String,
+    shifts: Vec<u8>,
+    variant: VigenereVariant,
+    alphabet: Alphabet,
+    mode: TextMode,
+}
+
+impl Vigenere {
+    pub fn new(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_mode(key, VigenereVariant::default(), TextMode::default())
+    }
+
+    pub fn beaufort(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_variant(key, VigenereVariant::Beaufort)
+    }
+
+    pub fn variant_beaufort(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_variant(key, VigenereVariant::VariantBeaufort)
+    }
+
+    pub fn gronsfeld(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_variant(key, VigenereVariant::Gronsfeld)
+    }
+
+    pub fn autokey(key: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_variant(key, VigenereVariant::Autokey)
+    }
+
+    pub fn with_variant(key: &str, variant: VigenereVariant) -> Result<Self, PolygraphiaError> {
+        Self::with_mode(key, variant, TextMode::default())
+    }
+
+    pub fn with_mode(
+        key: &str,
+        variant: VigenereVariant,
+        mode: TextMode,
+    ) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet_and_mode(key, variant, Alphabet::default(), mode)
+    }
+
+    pub fn with_alphabet(
+        key: &str,
+        variant: VigenereVariant,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        Self::with_alphabet_and_mode(key, variant, alphabet, TextMode::default())
+    }
+
+    pub fn with_alphabet_and_mode(
+        key: &str,
+        variant: VigenereVariant,
+        alphabet: Alphabet,
+        mode: TextMode,
+    ) -> Result<Self, PolygraphiaError> {
+        let (key, shifts) = Self::prepare_key(key, variant, &alphabet)?;
+        Ok(Vigenere {
+            key,
+            shifts,
+            variant,
+            alphabet,
+            mode,
+        })
+    }
+
+    pub fn random(length: usize) -> Result<Self, PolygraphiaError> {
+        Self::random_with(
+            length,
+            VigenereVariant::default(),
+            &mut KeyGenerator::new(),
+            Alphabet::default(),
+        )
+    }
+
+    pub fn random_with(
+        length: usize,
+        variant: VigenereVariant,
+        generator: &mut KeyGenerator,
+        alphabet: Alphabet,
+    ) -> Result<Self, PolygraphiaError> {
+        let key = if variant == VigenereVariant::Gronsfeld {
+            generator.word(&Alphabet::new("0123456789")?, length)
+        } else {
+            generator.word(&alphabet, length)
+        };
+        Self::with_alphabet(&key, variant, alphabet)
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn variant(&self) -> VigenereVariant {
+        self.variant
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn mode(&self) -> TextMode {
+        self.mode
+    }
+
+    pub fn set_key(&mut self, key: &str) -> Result<(), PolygraphiaError> {
+        let (key, shifts) = Self::prepare_key(key, self.variant, &self.alphabet)?;
+        self.key = key;
+        self.shifts = shifts;
+        Ok(())
+    }
+
+    pub fn set_mode(&mut self, mode: TextMode) {
+        self.mode = mode;
+    }
+
+    fn prepare_key(
+        key: &str,
+        variant: VigenereVariant,
+        alphabet: &Alphabet,
+    ) -> Result<(String, Vec<u8>), PolygraphiaError> {
+        let (key_clean, shifts): (String, Vec<u8>) = if variant == VigenereVariant::Gronsfeld {
+            if let Some(c) = key.chars().find(|c| !c.is_ascii_digit()) {
+                return Err(PolygraphiaError::InvalidKey(format!(
+                    "Gronsfeld key must contain only digits, found '{c}'"
+                )));
+            }
+            key.chars()
+                .map(|c| (c, ((c as u8 - b'0') as usize % alphabet.len()) as u8))
+                .unzip()
+        } else {
+            key.chars()
+                .filter_map(|c| alphabet.index_of(c))
+                .map(|idx| (alphabet.symbol(idx), idx as u8))
+                .unzip()
+        };
+        if shifts.is_empty() {
+            return Err(PolygraphiaError::InvalidKey(
+                "Key must contain at least one letter (or digit for Gronsfeld)".to_string(),
+            ));
+        }
+        Ok((key_clean, shifts))
+    }
+
+    fn process_index(&self, idx: u8, shift: u8, encrypt: bool) -> u8 {
+        let (idx, shift) = (idx as i16, shift as i16);
+        let modulus = self.alphabet.len() as i16;
+        let processed = match (self.variant, encrypt) {
+            (VigenereVariant::Beaufort, _) => shift - idx,
+            (VigenereVariant::VariantBeaufort, true) => idx - shift,
+            (VigenereVariant::VariantBeaufort, false) => idx + shift,
+            (_, true) => idx + shift,
+            (_, false) => idx - shift,
+        };
+        processed.rem_euclid(modulus) as u8
+    }
+
+    /// The character `c` becomes, or `None` if it is dropped, moving `key` on
+    /// past letters.
+    fn map_char(&self, c: char, encrypt: bool, key: &mut KeyStream) -> Option<char> {
+        let Some(idx) = self.alphabet.index_of(c) else {
+            return (self.mode == TextMode::PreserveAll).then_some(c);
+        };
+        let idx = idx as u8;
+        // Autokey letters extend the key; periodic variants just cycle it
+        let shift = if self.variant != VigenereVariant::Autokey {
+            self.shifts[key.position % self.shifts.len()]
+        } else if key.position < self.shifts.len() {
+            self.shifts[key.position]
+        } else {
+            key.autokey
+                .pop_front()
+                .expect("one autokey letter per key letter")
+        };
+        let processed = self.process_index(idx, shift, encrypt);
+        if self.variant == VigenereVariant::Autokey {
+            key.autokey.push_back(if encrypt { idx } else { processed });
+        }
+        key.position += 1;
+        Some(self.alphabet.symbol_like(processed as usize, c))
+    }
+
+    fn process_text(&self, text: &str, encrypt: bool, key: &mut KeyStream, out: &mut String) {
+        out.extend(text.chars().filter_map(|c| self.map_char(c, encrypt, key)));
+    }
+}
+
+/// How far a message has got through the key, with the plaintext letters an
+/// autokey has still to use.
+#[derive(Debug, Default, Clone)]
+struct KeyStream {
+    position: usize,
+    autokey: VecDeque<u8>,
+}
+
+impl Drop for KeyStream {
+    fn drop(&mut self) {
+        self.autokey.iter_mut().for_each(|k| *k = 0);
+    }
+}
+
+/// Stream state of `Vigenere`, which carries its key position from one piece
+/// to the next.
+#[derive(Debug, Default, Clone)]
+pub struct VigenereState {
+    scan: BlockState,
+    key: KeyStream,
+}
+
+impl Cipher for Vigenere {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(plaintext.len());
+        self.encrypt_into(plaintext, &mut result)?;
+        Ok(result)
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut result = String::with_capacity(ciphertext.len());
+        self.decrypt_into(ciphertext, &mut result)?;
+        Ok(result)
+    }
+
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        if plaintext.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(
+                "Empty plaintext".to_string(),
+            ));
+        }
+        let start = out.len();
+        self.process_text(plaintext, true, &mut KeyStream::default(), out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
+            return Err(PolygraphiaError::InvalidInput(
+                "Plaintext must contain at least one alphabetic character".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        if ciphertext.is_empty() {
+            return Err(PolygraphiaError::InvalidInput(
+                "Empty ciphertext".to_string(),
+            ));
+        }
+        let start = out.len();
+        self.process_text(ciphertext, false, &mut KeyStream::default(), out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
+            return Err(PolygraphiaError::InvalidInput(
+                "ciphertext must have at least one alphabetic character".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Works on `data` directly; only autokey, which keeps a key's worth of
+    /// plaintext letters, allocates.
+    fn encrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Plaintext", || {
+            let mut key = KeyStream::default();
+            move |c| self.map_char(c, true, &mut key)
+        })
+    }
+
+    fn decrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        text::map_in_place(data, self.name(), "Ciphertext", || {
+            let mut key = KeyStream::default();
+            move |c| self.map_char(c, false, &mut key)
+        })
+    }
+
+    fn name(&self) -> &str {
+        match self.variant {
+            VigenereVariant::Vigenere => "vigenere",
+            VigenereVariant::Beaufort => "beaufort",
+            VigenereVariant::VariantBeaufort => "variant-beaufort",
+            VigenereVariant::Gronsfeld => "gronsfeld",
+            VigenereVariant::Autokey => "autokey",
+        }
+    }
+}
+
+impl StreamCipher for Vigenere {
+    type State = VigenereState;
+
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut VigenereState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.scan.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], true, &mut state.key, out);
+        Ok(cut)
+    }
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut VigenereState,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError> {
+        let cut = state.scan.cut(text, 1, |c| self.alphabet.contains(c));
+        self.process_text(&text[..cut], false, &mut state.key, out);
+        Ok(cut)
+    }
+
+    fn encrypt_final(
+        &self,
+        text: &str,
+        state: &mut VigenereState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        let start = out.len();
+        self.process_text(text, true, &mut state.key, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
+            return Err(PolygraphiaError::InvalidInput(
+                "Plaintext must contain at least one alphabetic character".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        state: &mut VigenereState,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError> {
+        let start = out.len();
+        self.process_text(text, false, &mut state.key, out);
+        if self.mode == TextMode::AlphaOnly && out.len() == start {
+            return Err(PolygraphiaError::InvalidInput(
+                "ciphertext must have at least one alphabetic character".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Vigenere {
+    fn drop(&mut self) {
+        self.shifts.iter_mut().for_each(|k| *k = 0);
+        self.key.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vigenere_new() {
+        let cipher = Vigenere::new("Lemon").unwrap();
+        assert_eq!(cipher.key(), "lemon");
+        assert_eq!(cipher.variant(), VigenereVariant::Vigenere);
+        assert_eq!(cipher.mode(), TextMode::PreserveAll);
+    }
+
+    #[test]
+    fn test_vigenere_invalid_keys() {
+        assert!(Vigenere::new("").is_err());
+        assert!(Vigenere::new("123").is_err());
+        assert!(Vigenere::gronsfeld("").is_err());
+        // Gronsfeld keys are numeric only
+        assert!(Vigenere::gronsfeld("31a5").is_err());
+    }
+
+    #[test]
+    fn test_vigenere_encrypt_decrypt() {
+        // Classic textbook example
+        let cipher = Vigenere::new("lemon").unwrap();
+        assert_eq!(cipher.encrypt("attackatdawn").unwrap(), "lxfopvefrnhr");
+        assert_eq!(cipher.decrypt("lxfopvefrnhr").unwrap(), "attackatdawn");
+    }
+
+    #[test]
+    fn test_vigenere_preserve_all() {
+        let cipher = Vigenere::new("lemon").unwrap();
+
+        // Non-letters are kept and do not consume key letters
+        assert_eq!(
+            cipher.encrypt("Attack at Dawn!").unwrap(),
+            "Lxfopv ef Rnhr!"
+        );
+        assert_eq!(
+            cipher.decrypt("Lxfopv ef Rnhr!").unwrap(),
+            "Attack at Dawn!"
+        );
+    }
+
+    #[test]
+    fn test_vigenere_alpha_only() {
+        let cipher =
+            Vigenere::with_mode("lemon", VigenereVariant::Vigenere, TextMode::AlphaOnly).unwrap();
+        assert_eq!(cipher.encrypt("Attack at Dawn!").unwrap(), "LxfopvefRnhr");
+        assert!(cipher.encrypt("123!@#").is_err());
+    }
+
+    #[test]
+    fn test_beaufort() {
+        let cipher = Vigenere::beaufort("fortification").unwrap();
+        let encrypted = cipher.encrypt("defendtheeastwallofthecastle").unwrap();
+        assert_eq!(encrypted, "ckmpvcpvwpiwujogiuapvwriwuuk");
+        assert_eq!(
+            cipher.decrypt(&encrypted).unwrap(),
+            "defendtheeastwallofthecastle"
+        );
+
+        // Beaufort is reciprocal
+        assert_eq!(
+            cipher.encrypt(&encrypted).unwrap(),
+            "defendtheeastwallofthecastle"
+        );
+    }
+
+    #[test]
+    fn test_variant_beaufort() {
+        let cipher = Vigenere::variant_beaufort("lemon").unwrap();
+        let vigenere = Vigenere::new("lemon").unwrap();
+
+        // Variant Beaufort encryption is Vigenere decryption
+        assert_eq!(
+            cipher.encrypt("attackatdawn").unwrap(),
+            vigenere.decrypt("attackatdawn").unwrap()
+        );
+        let encrypted = cipher.encrypt("Attack at Dawn!").unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "Attack at Dawn!");
+    }
+
+    #[test]
+    fn test_gronsfeld() {
+        let cipher = Vigenere::gronsfeld("31415").unwrap();
+        assert_eq!(cipher.key(), "31415");
+        assert_eq!(cipher.encrypt("hello").unwrap(), "kfpmt");
+        assert_eq!(cipher.decrypt("kfpmt").unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_autokey() {
+        let cipher = Vigenere::autokey("queenly").unwrap();
+        let encrypted = cipher.encrypt("attackatdawn").unwrap();
+        assert_eq!(encrypted, "qnxepvytwtwp");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "attackatdawn");
+    }
+
+    #[test]
+    fn test_autokey_preserve_all_roundtrip() {
+        let cipher = Vigenere::autokey("key").unwrap();
+        let plaintext = "The Quick Brown Fox, 123!";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_vigenere_set_key() {
+        let mut cipher = Vigenere::new("lemon").unwrap();
+        let enc1 = cipher.encrypt("hello").unwrap();
+
+        cipher.set_key("key").unwrap();
+        let enc2 = cipher.encrypt("hello").unwrap();
+
+        assert_ne!(enc1, enc2);
+        assert!(cipher.set_key("").is_err());
+    }
+
+    #[test]
+    fn test_vigenere_set_mode() {
+        let mut cipher = Vigenere::new("key").unwrap();
+        assert_eq!(cipher.encrypt("hello123").unwrap(), "rijvs123");
+
+        cipher.set_mode(TextMode::AlphaOnly);
+        assert_eq!(cipher.encrypt("hello123").unwrap(), "rijvs");
+    }
+
+    #[test]
+    fn test_vigenere_empty_input() {
+        let cipher = Vigenere::new("key").unwrap();
+        assert!(cipher.encrypt("").is_err());
+        assert!(cipher.decrypt("").is_err());
+    }
+
+    #[test]
+    fn test_vigenere_custom_alphabet() {
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456789").unwrap();
+        let cipher = Vigenere::with_alphabet("key9", VigenereVariant::Vigenere, alphabet).unwrap();
+        assert_eq!(cipher.key(), "key9");
+        assert_eq!(cipher.alphabet().len(), 36);
+
+        // Digits carry no case, so letters that become digits come back lowercase
+        let plaintext = "room 101, floor 3!";
+        let encrypted = cipher.encrypt(plaintext).unwrap();
+        assert_ne!(&encrypted[5..8], "101");
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_vigenere_single_letter_key_matches_caesar() {
+        use crate::classic::Caesar;
+
+        let vigenere = Vigenere::new("d").unwrap();
+        let caesar = Caesar::new(3).unwrap();
+        let plaintext = "Hello, World!";
+        assert_eq!(
+            vigenere.encrypt(plaintext).unwrap(),
+            caesar.encrypt(plaintext).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_vigenere_random() {
+        let cipher = Vigenere::random(12).unwrap();
+        assert_eq!(cipher.key().len(), 12);
+
+        let mut generator = KeyGenerator::from_seed(1);
+        let gronsfeld = Vigenere::random_with(
+            8,
+            VigenereVariant::Gronsfeld,
+            &mut generator,
+            Alphabet::english(),
+        )
+        .unwrap();
+        assert!(gronsfeld.key().chars().all(|c| c.is_ascii_digit()));
+
+        assert!(Vigenere::random(0).is_err());
+    }
+
+    #[test]
+    fn test_vigenere_bytes_in_place() {
+        for cipher in [
+            Vigenere::new("lemon").unwrap(),
+            Vigenere::autokey("queen").unwrap(),
+        ] {
+            let mut data = b"Attack at dawn!".to_vec();
+            let (ptr, capacity) = (data.as_ptr(), data.capacity());
+            cipher.encrypt_bytes_in_place(&mut data).unwrap();
+            assert_eq!(data, cipher.encrypt("Attack at dawn!").unwrap().as_bytes());
+            assert_eq!((data.as_ptr(), data.capacity()), (ptr, capacity));
+            cipher.decrypt_bytes_in_place(&mut data).unwrap();
+            assert_eq!(data, b"Attack at dawn!");
+        }
+    }
+
+    #[test]
+    fn test_vigenere_cipher_trait() {
+        let ciphers: Vec<Box<dyn Cipher>> = vec![
+            Box::new(Vigenere::new("key").unwrap()),
+            Box::new(Vigenere::beaufort("key").unwrap()),
+            Box::new(Vigenere::variant_beaufort("key").unwrap()),
+            Box::new(Vigenere::gronsfeld("123").unwrap()),
+            Box::new(Vigenere::autokey("key").unwrap()),
+        ];
+        let names: Vec<&str> = ciphers.iter().map(|c| c.name()).collect();
+        assert_eq!(
+            names,
+            [
+                "vigenere",
+                "beaufort",
+                "variant-beaufort",
+                "gronsfeld",
+                "autokey"
+            ]
+        );
+        for cipher in &ciphers {
+            let encrypted = cipher.encrypt("Hello, World!").unwrap();
+            assert_eq!(cipher.decrypt(&encrypted).unwrap(), "Hello, World!");
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use rand::Rng;
+
+use crate::analysis::NGramScorer;
+use crate::classic::Playfair;
+use crate::cryptanalysis::Candidate;
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::{Alphabet, KeyGenerator};
+
+const SIDE: usize = 5;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoolingSchedule {
+    /// Temperature falls by the same amount every iteration.
+    #[default]
+    Linear,
+    /// Temperature falls by the same factor every iteration.
+    Exponential,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnealingOptions {
+    /// Scale of the score drops accepted early on. The default suits a few
+    /// hundred letters of ciphertext scored with quadgrams.
+    pub initial_temperature: f64,
+    pub final_temperature: f64,
+    pub schedule: CoolingSchedule,
+    /// Iterations per run.
+    pub iterations: usize,
+    /// Independent runs from fresh random keys; the best result wins.
+    pub restarts: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for AnnealingOptions {
+    fn default() -> Self {
+        AnnealingOptions {
+            initial_temperature: 20.0,
+            final_temperature: 0.5,
+            schedule: CoolingSchedule::default(),
+            iterations: 1_000_000,
+            restarts: 3,
+            seed: None,
+        }
+    }
+}
+
+impl AnnealingOptions {
+    fn temperature(&self, iteration: usize) -> f64 {
+        let progress = iteration as f64 / self.iterations.max(1) as f64;
+        match self.schedule {
+            CoolingSchedule::Linear => {
+                self.initial_temperature
+                    + (self.final_temperature - self.initial_temperature) * progress
+            }
+            CoolingSchedule::Exponential => {
+                let ratio = self.final_temperature / self.initial_temperature;
+                self.initial_temperature * ratio.powf(progress)
+            }
+        }
+    }
+}
+
+/// Recovers a 5x5 Playfair square from ciphertext alone.
+///
+/// Keys are scored by `scorer` on the decrypted letters; higher is better.
+/// The returned plaintext keeps separators and padding, as with `Unpadding::Keep`.
+pub fn solve_playfair(
+    ciphertext: &str,
+    scorer: &NGramScorer,
+    options: &AnnealingOptions,
+) -> Result<Candidate<Playfair>, PolygraphiaError> {
+    let alphabet = Alphabet::english_merged_ij();
+    let mut letters: Vec<u8> = ciphertext
+        .chars()
+        .filter_map(|c| alphabet.index_of(c))
+        .map(|idx| idx as u8)
+        .collect();
+    letters.truncate(letters.len() / 2 * 2);
+    if letters.len() < 2 {
+        return Err(PolygraphiaError::InvalidInput(
+            "Ciphertext must contain at least two letters".to_string(),
+        ));
+    }
+    let to_scorer: Vec<u8> = alphabet
+        .symbols()
+        .iter()
+        .map(|&c| scorer.alphabet().index_of(c).map(|idx| idx as u8))
+        .collect::<Option<_>>()
+        .ok_or_else(|| {
+            PolygraphiaError::InvalidInput(
+                "Scorer alphabet does not cover the Playfair alphabet".to_string(),
+            )
+        })?;
+
+    let mut generator = match options.seed {
+        Some(seed) => KeyGenerator::from_seed(seed),
+        None => KeyGenerator::new(),
+    };
+    let mut state = Search {
+        letters,
+        to_scorer,
+        scorer,
+        plaintext: Vec::new(),
+    };
+
+    let mut best: Option<(Square, f64)> = None;
+    for _ in 0..options.restarts.max(1) {
+        let start = Square::from_permutation(&generator.permutation(&alphabet), &alphabet);
+        let (square, score) = state.anneal(start, options, &mut generator);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_score)| score > *best_score)
+        {
+            best = Some((square, score));
+        }
+    }
+
+    let (square, score) = best.expect("at least one restart");
+    let key = Playfair::with_alphabet(&square.to_key(&alphabet), alphabet)?;
+    Ok(Candidate {
+        plaintext: key.decrypt(ciphertext)?,
+        key,
+        score,
+    })
+}
+
+struct Search<'a> {
+    letters: Vec<u8>,
+    to_scorer: Vec<u8>,
+    scorer: &'a NGramScorer,
+    plaintext: Vec<u8>,
+}
+
+impl Search<'_> {
+    fn anneal(
+        &mut self,
+        start: Square,
+        options: &AnnealingOptions,
+        generator: &mut KeyGenerator,
+    ) -> (Square, f64) {
+        let mut current = start;
+        let mut current_score = self.score(&current);
+        let mut best = current.clone();
+        let mut best_score = current_score;
+        for iteration in 0..options.iterations {
+            let temperature = options.temperature(iteration);
+            let candidate = current.mutate(generator);
+            let score = self.score(&candidate);
+            let delta = score - current_score;
+            let accept = delta >= 0.0
+                || (temperature > 0.0
+                    && generator.rng().random::<f64>() < (delta / temperature).exp());
+            if accept {
+                current = candidate;
+                current_score = score;
+                if current_score > best_score {
+                    best = current.clone();
+                    best_score = current_score;
+                }
+            }
+        }
+        (best, best_score)
+    }
+
+    fn score(&mut self, square: &Square) -> f64 {
+        self.plaintext.clear();
+        for pair in self.letters.chunks(2) {
+            let (a, b) = square.decrypt_pair(pair[0], pair[1]);
+            self.plaintext.push(self.to_scorer[a as usize]);
+            self.plaintext.push(self.to_scorer[b as usize]);
+        }
+        self.scorer.score_indices(&self.plaintext)
+    }
+}
+
+/// A Playfair square as cell contents plus the reverse lookup.
+#[derive(Debug, Clone)]
+struct Square {
+    cells: [u8; SIDE * SIDE],
+    positions: [u8; SIDE * SIDE],
+}
+
+impl Square {
+    fn from_permutation(permutation: &str, alphabet: &Alphabet) -> Self {
+        let mut cells = [0; SIDE * SIDE];
+        for (cell, c) in cells.iter_mut().zip(permutation.chars()) {
+            *cell = alphabet.index_of(c).unwrap_or(0) as u8;
+        }
+        Self::from_cells(cells)
+    }
+
+    fn from_cells(cells: [u8; SIDE * SIDE]) -> Self {
+        let mut positions = [0; SIDE * SIDE];
+        for (pos, &symbol) in cells.iter().enumerate() {
+            positions[symbol as usize] = pos as u8;
+        }
+        Square { cells, positions }
+    }
+
+    fn to_key(&self, alphabet: &Alphabet) -> String {
+        self.cells
+            .iter()
+            .map(|&idx| alphabet.symbol(idx as usize))
+            .collect()
+    }
+
+    fn decrypt_pair(&self, a: u8, b: u8) -> (u8, u8) {
+        let (pa, pb) = (
+            self.positions[a as usize] as usize,
+            self.positions[b as usize] as usize,
+        );
+        let (ra, ca, rb, cb) = (pa / SIDE, pa % SIDE, pb / SIDE, pb % SIDE);
+        let (ra, ca, rb, cb) = if ra == rb {
+            (ra, (ca + SIDE - 1) % SIDE, rb, (cb + SIDE - 1) % SIDE)
+        } else if ca == cb {
+            ((ra + SIDE - 1) % SIDE, ca, (rb + SIDE - 1) % SIDE, cb)
+        } else {
+            (ra, cb, rb, ca)
+        };
+        (self.cells[ra * SIDE + ca], self.cells[rb * SIDE + cb])
+    }
+
+    fn mutate(&self, generator: &mut KeyGenerator) -> Self {
+        let rng = generator.rng();
+        let mut cells = self.cells;
+        let (i, j) = (rng.random_range(0..SIDE), rng.random_range(0..SIDE));
+        match rng.random_range(0..50) {
+            0 => {
+                for col in 0..SIDE {
+                    cells.swap(i * SIDE + col, j * SIDE + col);
+                }
+            }
+            1 => {
+                for row in 0..SIDE {
+                    cells.swap(row * SIDE + i, row * SIDE + j);
+                }
+            }
+            2 => cells.reverse(),
+            3 => {
+                for row in cells.chunks_mut(SIDE) {
+                    row.reverse();
+                }
+            }
+            4 => {
+                for row in 0..SIDE {
+                    for col in row + 1..SIDE {
+                        cells.swap(row * SIDE + col, col * SIDE + row);
+                    }
+                }
+            }
+            _ => {
+                let (a, b) = (
+                    rng.random_range(0..SIDE * SIDE),
+                    rng.random_range(0..SIDE * SIDE),
+                );
+                cells.swap(a, b);
+            }
+        }
+        Self::from_cells(cells)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age of \
+        wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of Light, it was the season of Darkness, it was the \
+        spring of hope, it was the winter of despair, we had everything before us, we had \
+        nothing before us, we were all going direct to Heaven, we were all going direct the \
+        other way.";
+
+    #[test]
+    #[ignore = "a full annealing run takes about a minute unoptimized"]
+    fn test_solve_playfair() {
+        let playfair = Playfair::new("charles dickens").unwrap();
+        let ciphertext = playfair.encrypt(PLAINTEXT).unwrap();
+        let scorer = NGramScorer::english_quadgrams();
+        let options = AnnealingOptions {
+            restarts: 1,
+            seed: Some(0),
+            ..AnnealingOptions::default()
+        };
+
+        let solved = solve_playfair(&ciphertext, &scorer, &options).unwrap();
+        assert_eq!(
+            solved.key.encrypt(PLAINTEXT).unwrap(),
+            ciphertext,
+            "recovered square should be equivalent to the original"
+        );
+        assert!(solved.plaintext.starts_with("It was the best of times"));
+        assert!(solved.score <= 0.0);
+    }
+
+    #[test]
+    fn test_solve_playfair_short_run() {
+        let playfair = Playfair::new("charles dickens").unwrap();
+        let ciphertext = playfair.encrypt(&PLAINTEXT[..120]).unwrap();
+        let scorer = NGramScorer::english_quadgrams();
+        let options = AnnealingOptions {
+            iterations: 5_000,
+            restarts: 2,
+            seed: Some(0),
+            ..AnnealingOptions::default()
+        };
+
+        let solved = solve_playfair(&ciphertext, &scorer, &options).unwrap();
+        assert_eq!(solved.key.decrypt(&ciphertext).unwrap(), solved.plaintext);
+        assert!((solved.score - scorer.score(&solved.plaintext)).abs() < 1e-6);
+        assert!(solved.score > scorer.score(&ciphertext));
+    }
+
+    #[test]
+    fn test_temperature_schedule() {
+        let mut options = AnnealingOptions {
+            initial_temperature: 10.0,
+            final_temperature: 1.0,
+            iterations: 100,
+            ..AnnealingOptions::default()
+        };
+        assert_eq!(options.temperature(0), 10.0);
+        assert!((options.temperature(50) - 5.5).abs() < 1e-9);
+        options.schedule = CoolingSchedule::Exponential;
+        assert!((options.temperature(50) - 10f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_solve_playfair_rejects_short_input() {
+        let scorer = NGramScorer::english_quadgrams();
+        assert!(solve_playfair("a", &scorer, &AnnealingOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_square_decrypt_matches_playfair() {
+        let alphabet = Alphabet::english_merged_ij();
+        let playfair = Playfair::new("playfair example").unwrap();
+        let square = Square::from_permutation(playfair.key(), &alphabet);
+        let ciphertext = playfair.encrypt("hidethegoldinthetreestump").unwrap();
+        let letters: Vec<u8> = ciphertext
+            .chars()
+            .filter_map(|c| alphabet.index_of(c))
+            .map(|idx| idx as u8)
+            .collect();
+        let decrypted: String = letters
+            .chunks(2)
+            .flat_map(|pair| {
+                let (a, b) = square.decrypt_pair(pair[0], pair[1]);
+                [alphabet.symbol(a as usize), alphabet.symbol(b as usize)]
+            })
+            .collect();
+        assert_eq!(decrypted, playfair.decrypt(&ciphertext).unwrap());
+    }
+}
@@ -0,0 +1,125 @@
+use crate::analysis;
+use crate::classic::{Affine, Caesar};
+use crate::cryptanalysis::Candidate;
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::{Alphabet, math};
+
+/// Tries every Caesar shift over the English alphabet.
+///
+/// Candidates are sorted by chi-squared score, lowest (most English-like) first.
+pub fn brute_force_caesar(ciphertext: &str) -> Result<Vec<Candidate<u8>>, PolygraphiaError> {
+    brute_force_caesar_with(ciphertext, &Alphabet::english())
+}
+
+pub fn brute_force_caesar_with(
+    ciphertext: &str,
+    alphabet: &Alphabet,
+) -> Result<Vec<Candidate<u8>>, PolygraphiaError> {
+    let expected = expected_frequencies(alphabet)?;
+    let mut candidates = Vec::with_capacity(alphabet.len());
+    for shift in 0..alphabet.len() as u8 {
+        let cipher = Caesar::with_alphabet(shift, alphabet.clone())?;
+        candidates.push(score(
+            shift,
+            cipher.decrypt(ciphertext)?,
+            alphabet,
+            expected,
+        ));
+    }
+    Ok(ranked(candidates))
+}
+
+/// Tries every Affine key over the English alphabet. Keys are `(shift, multiplier)`,
+/// in the same order as `Affine::new`.
+///
+/// Candidates are sorted by chi-squared score, lowest (most English-like) first.
+pub fn brute_force_affine(ciphertext: &str) -> Result<Vec<Candidate<(u8, u8)>>, PolygraphiaError> {
+    brute_force_affine_with(ciphertext, &Alphabet::english())
+}
+
+pub fn brute_force_affine_with(
+    ciphertext: &str,
+    alphabet: &Alphabet,
+) -> Result<Vec<Candidate<(u8, u8)>>, PolygraphiaError> {
+    let expected = expected_frequencies(alphabet)?;
+    let modulus = alphabet.len() as u8;
+    let mut candidates = Vec::new();
+    for multiplier in (1..modulus).filter(|&m| math::are_coprime(m as u64, modulus as u64)) {
+        for shift in 0..modulus {
+            let cipher = Affine::with_alphabet(shift, multiplier, alphabet.clone())?;
+            let plaintext = cipher.decrypt(ciphertext)?;
+            candidates.push(score((shift, multiplier), plaintext, alphabet, expected));
+        }
+    }
+    Ok(ranked(candidates))
+}
+
+pub(crate) fn expected_frequencies(
+    alphabet: &Alphabet,
+) -> Result<&'static [f64], PolygraphiaError> {
+    analysis::reference_frequencies(alphabet).ok_or_else(|| {
+        PolygraphiaError::InvalidInput(
+            "No reference letter frequencies for this alphabet".to_string(),
+        )
+    })
+}
+
+fn score<K>(key: K, plaintext: String, alphabet: &Alphabet, expected: &[f64]) -> Candidate<K> {
+    let counts = analysis::letter_counts(&plaintext, alphabet);
+    Candidate {
+        key,
+        score: analysis::chi_squared(&counts, expected),
+        plaintext,
+    }
+}
+
+fn ranked<K>(mut candidates: Vec<Candidate<K>>) -> Vec<Candidate<K>> {
+    candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, \
+        it was the age of wisdom, it was the age of foolishness.";
+
+    #[test]
+    fn test_brute_force_caesar() {
+        let ciphertext = Caesar::new(17).unwrap().encrypt(PLAINTEXT).unwrap();
+        let candidates = brute_force_caesar(&ciphertext).unwrap();
+        assert_eq!(candidates.len(), 26);
+        assert_eq!(candidates[0].key, 17);
+        assert_eq!(candidates[0].plaintext, PLAINTEXT);
+        assert!(candidates.windows(2).all(|w| w[0].score <= w[1].score));
+    }
+
+    #[test]
+    fn test_brute_force_affine() {
+        let ciphertext = Affine::new(8, 15).unwrap().encrypt(PLAINTEXT).unwrap();
+        let candidates = brute_force_affine(&ciphertext).unwrap();
+        assert_eq!(candidates.len(), 312);
+        assert_eq!(candidates[0].key, (8, 15));
+        assert_eq!(candidates[0].plaintext, PLAINTEXT);
+    }
+
+    #[test]
+    fn test_brute_force_arabic() {
+        let plaintext = "ذهب الولد الى المدرسة في الصباح الباكر مع اصدقائه وكان سعيدا جدا";
+        let ciphertext = Caesar::with_alphabet(5, Alphabet::arabic())
+            .unwrap()
+            .encrypt(plaintext)
+            .unwrap();
+        let candidates = brute_force_caesar_with(&ciphertext, &Alphabet::arabic()).unwrap();
+        assert_eq!(candidates.len(), 28);
+        assert_eq!(candidates[0].key, 5);
+    }
+
+    #[test]
+    fn test_brute_force_unknown_alphabet() {
+        let alphabet = Alphabet::new("0123456789").unwrap();
+        assert!(brute_force_caesar_with("12345", &alphabet).is_err());
+    }
+}
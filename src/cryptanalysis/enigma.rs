@@ -0,0 +1,378 @@
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+use crate::analysis::NGramScorer;
+use crate::classic::{Enigma, Reflector, Rotor};
+use crate::cryptanalysis::Candidate;
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::TextMode;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnigmaAttackOptions {
+    /// Rotors that may fill the three slots; every ordered choice of three is
+    /// tried. The Greek wheels are not supported.
+    pub rotors: Vec<Rotor>,
+    pub reflectors: Vec<Reflector>,
+    /// Rotor settings kept from the position search for the ring and
+    /// plugboard stages.
+    pub candidates: usize,
+    pub max_plugs: usize,
+    /// Worker threads; 0 uses every available core.
+    pub threads: usize,
+}
+
+impl Default for EnigmaAttackOptions {
+    fn default() -> Self {
+        EnigmaAttackOptions {
+            rotors: vec![Rotor::I, Rotor::II, Rotor::III, Rotor::IV, Rotor::V],
+            reflectors: vec![Reflector::B],
+            candidates: 10,
+            max_plugs: 10,
+            threads: 0,
+        }
+    }
+}
+
+/// Recovers the settings of a three-rotor Enigma from ciphertext alone, after
+/// Gillogly and Williams:
+///
+/// 1. every rotor order and starting position is tried with the rings at A and
+///    no plugs, keeping the settings whose output has the highest index of
+///    coincidence;
+/// 2. the ring settings of the right and middle rotors are found the same way,
+///    turning each position along with its ring so the wiring stays aligned;
+/// 3. plugs are added, moved and removed by hill climbing, on the index of
+///    coincidence and then on `scorer`.
+///
+/// Only A-Z count as letters, and `scorer` must use the English alphabet. The
+/// result is scored by `scorer`; higher is better. The recovered key can
+/// differ from the original in ring settings and positions together, and in
+/// the left ring, while giving the same plaintext.
+///
+/// Like the original attack, this needs long messages and few plugs: with the
+/// rings at A the middle rotor turns at the wrong letter, and when the right
+/// ring is far from A that garbles most of the text before stage 1 can see it.
+pub fn solve_enigma(
+    ciphertext: &str,
+    scorer: &NGramScorer,
+    options: &EnigmaAttackOptions,
+) -> Result<Candidate<Enigma>, PolygraphiaError> {
+    if scorer.alphabet().len() != 26 {
+        return Err(PolygraphiaError::InvalidInput(
+            "The Enigma attack needs a scorer over the English alphabet".to_string(),
+        ));
+    }
+    let letters: Vec<u8> = ciphertext
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase() as u8 - b'A')
+        .collect();
+    if letters.len() < 2 {
+        return Err(PolygraphiaError::InvalidInput(
+            "Ciphertext must contain at least two letters A-Z".to_string(),
+        ));
+    }
+    let orders = rotor_orders(&options.rotors)?;
+    if options.reflectors.is_empty() {
+        return Err(PolygraphiaError::InvalidInput(
+            "At least one reflector is needed".to_string(),
+        ));
+    }
+    let mut machines = Vec::with_capacity(orders.len() * options.reflectors.len());
+    for &reflector in &options.reflectors {
+        for &order in &orders {
+            machines.push(Enigma::new(order, reflector)?);
+        }
+    }
+    let threads = match options.threads {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        n => n,
+    };
+    let keep = options.candidates.max(1);
+    let max_plugs = options.max_plugs;
+
+    let mut settings: Vec<(f64, Enigma)> = parallel_map(&machines, threads, |machine| {
+        best_positions(machine, &letters, keep)
+    })
+    .into_iter()
+    .flatten()
+    .collect();
+    settings.sort_by(|a, b| b.0.total_cmp(&a.0));
+    settings.truncate(keep);
+
+    let solved = parallel_map(&settings, threads, |(_, machine)| {
+        let mut machine = machine.clone();
+        let mut buffer = Vec::with_capacity(letters.len());
+        let ioc = |text: &[u8]| index_of_coincidence(text);
+        let ngrams = |text: &[u8]| scorer.score_indices(text);
+        find_rings(&mut machine, &letters, &mut buffer, ioc);
+        climb_plugs(&mut machine, &letters, max_plugs, &mut buffer, ioc);
+        climb_plugs(&mut machine, &letters, max_plugs, &mut buffer, ngrams);
+        // A ring one step out only moves a turnover by one letter, which the
+        // index of coincidence barely notices
+        find_rings(&mut machine, &letters, &mut buffer, ngrams);
+        let score = climb_plugs(&mut machine, &letters, max_plugs, &mut buffer, ngrams);
+        (score, machine)
+    });
+    let (score, mut key) = solved
+        .into_iter()
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .expect("at least one rotor setting");
+    key.set_mode(TextMode::PreserveAll);
+    Ok(Candidate {
+        plaintext: key.decrypt(ciphertext)?,
+        key,
+        score,
+    })
+}
+
+/// Every ordered choice of three different rotors, left to right.
+fn rotor_orders(rotors: &[Rotor]) -> Result<Vec<[Rotor; 3]>, PolygraphiaError> {
+    let mut orders = Vec::new();
+    for &left in rotors {
+        for &middle in rotors {
+            for &right in rotors {
+                if left != middle && left != right && middle != right {
+                    orders.push([left, middle, right]);
+                }
+            }
+        }
+    }
+    if orders.is_empty() {
+        return Err(PolygraphiaError::InvalidInput(
+            "At least three different rotors are needed".to_string(),
+        ));
+    }
+    Ok(orders)
+}
+
+/// Runs `f` over `items` on `threads` workers, which take the next item as
+/// they become free. Results come back in the order of `items`.
+fn parallel_map<T, R, F>(items: &[T], threads: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let workers = threads.clamp(1, items.len().max(1));
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            return done;
+                        };
+                        done.push((i, f(item)));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("search worker panicked"))
+            .collect()
+    });
+    results.sort_by_key(|&(i, _)| i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+fn index_of_coincidence(letters: &[u8]) -> f64 {
+    let mut counts = [0usize; 26];
+    for &c in letters {
+        counts[c as usize] += 1;
+    }
+    let total = letters.len();
+    let matches: usize = counts.iter().map(|&n| n * n.saturating_sub(1)).sum();
+    matches as f64 / (total * (total - 1)) as f64
+}
+
+/// The `keep` starting positions of `machine` with the highest index of
+/// coincidence, best first.
+fn best_positions(machine: &Enigma, letters: &[u8], keep: usize) -> Vec<(f64, Enigma)> {
+    let mut machine = machine.clone();
+    let mut buffer = Vec::with_capacity(letters.len());
+    let mut best: Vec<(f64, [u8; 3])> = Vec::with_capacity(keep + 1);
+    for position in 0..26 * 26 * 26 {
+        let window = [
+            (position / 676) as u8,
+            (position / 26 % 26) as u8,
+            (position % 26) as u8,
+        ];
+        machine.positions.copy_from_slice(&window);
+        machine.process_indices(letters, &mut buffer);
+        let score = index_of_coincidence(&buffer);
+        if best.len() < keep || score > best[best.len() - 1].0 {
+            let at = best.partition_point(|&(s, _)| s >= score);
+            best.insert(at, (score, window));
+            best.truncate(keep);
+        }
+    }
+    best.into_iter()
+        .map(|(score, window)| {
+            machine.positions.copy_from_slice(&window);
+            (score, machine.clone())
+        })
+        .collect()
+}
+
+/// Sets the right and then the middle ring. Turning a ring and its position
+/// together leaves the wiring where it was and only moves the turnover.
+fn find_rings(
+    machine: &mut Enigma,
+    letters: &[u8],
+    buffer: &mut Vec<u8>,
+    score: impl Fn(&[u8]) -> f64,
+) {
+    for slot in [2, 1] {
+        let (ring, position) = (machine.rings[slot], machine.positions[slot]);
+        let mut best = (f64::NEG_INFINITY, 0);
+        for shift in 0..26 {
+            machine.rings[slot] = (ring + shift) % 26;
+            machine.positions[slot] = (position + shift) % 26;
+            machine.process_indices(letters, buffer);
+            let candidate = score(buffer);
+            if candidate > best.0 {
+                best = (candidate, shift);
+            }
+        }
+        machine.rings[slot] = (ring + best.1) % 26;
+        machine.positions[slot] = (position + best.1) % 26;
+    }
+}
+
+/// Plugboard with `a` and `b` plugged together. Their old partners are
+/// plugged to each other, or freed if `a` and `b` were already a pair.
+fn replug(plugboard: &[u8; 26], a: u8, b: u8) -> [u8; 26] {
+    let mut result = *plugboard;
+    let (partner_a, partner_b) = (plugboard[a as usize], plugboard[b as usize]);
+    if partner_a == b {
+        result[a as usize] = a;
+        result[b as usize] = b;
+        return result;
+    }
+    let crossed = partner_a != a && partner_b != b;
+    result[partner_a as usize] = if crossed { partner_b } else { partner_a };
+    result[partner_b as usize] = if crossed { partner_a } else { partner_b };
+    result[a as usize] = b;
+    result[b as usize] = a;
+    result
+}
+
+fn plug_count(plugboard: &[u8; 26]) -> usize {
+    (0..26u8).filter(|&c| plugboard[c as usize] > c).count()
+}
+
+/// Applies the single plug change that raises `score` most, until none does.
+fn climb_plugs(
+    machine: &mut Enigma,
+    letters: &[u8],
+    max_plugs: usize,
+    buffer: &mut Vec<u8>,
+    score: impl Fn(&[u8]) -> f64,
+) -> f64 {
+    machine.process_indices(letters, buffer);
+    let mut current = score(buffer);
+    loop {
+        let original = machine.plugboard;
+        let mut best: Option<(f64, [u8; 26])> = None;
+        for a in 0..26 {
+            for b in a + 1..26 {
+                let plugboard = replug(&original, a, b);
+                if plug_count(&plugboard) > max_plugs {
+                    continue;
+                }
+                machine.plugboard = plugboard;
+                machine.process_indices(letters, buffer);
+                let candidate = score(buffer);
+                if candidate > best.map_or(current, |(s, _)| s) {
+                    best = Some((candidate, plugboard));
+                }
+            }
+        }
+        match best {
+            Some((improved, plugboard)) => {
+                machine.plugboard = plugboard;
+                current = improved;
+            }
+            None => {
+                machine.plugboard = original;
+                return current;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age of \
+        wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of Light, it was the season of Darkness, it was the \
+        spring of hope, it was the winter of despair, we had everything before us, we had \
+        nothing before us, we were all going direct to Heaven, we were all going direct the \
+        other way.";
+
+    #[test]
+    #[ignore = "searches every rotor order and position; slow unoptimized"]
+    fn test_solve_enigma() {
+        let mut cipher = Enigma::new([Rotor::III, Rotor::I, Rotor::II], Reflector::B).unwrap();
+        cipher.set_ring_settings("CHK").unwrap();
+        cipher.set_positions("QEV").unwrap();
+        cipher.set_plugboard("AM FI NV PS TU WZ").unwrap();
+        let ciphertext = cipher.encrypt(PLAINTEXT).unwrap();
+
+        let options = EnigmaAttackOptions {
+            rotors: vec![Rotor::I, Rotor::II, Rotor::III],
+            threads: 2,
+            ..EnigmaAttackOptions::default()
+        };
+        let scorer = NGramScorer::english_quadgrams();
+        let solved = solve_enigma(&ciphertext, &scorer, &options).unwrap();
+        assert_eq!(solved.plaintext, PLAINTEXT);
+        assert_eq!(solved.key.rotors(), cipher.rotors());
+        assert_eq!(solved.key.plugboard(), cipher.plugboard());
+    }
+
+    #[test]
+    fn test_replug() {
+        let none: [u8; 26] = std::array::from_fn(|i| i as u8);
+        let ab = replug(&none, 0, 1);
+        assert_eq!(plug_count(&ab), 1);
+        assert_eq!(replug(&ab, 0, 1), none);
+
+        // Moving a plug: A-B then A-C leaves B free
+        let ac = replug(&ab, 0, 2);
+        assert_eq!((ac[0], ac[1], ac[2]), (2, 1, 0));
+
+        // Crossing two plugs keeps both
+        let cd = replug(&ab, 2, 3);
+        let crossed = replug(&cd, 0, 2);
+        assert_eq!((crossed[0], crossed[1], crossed[2]), (2, 3, 0));
+        assert_eq!(plug_count(&crossed), 2);
+    }
+
+    #[test]
+    fn test_parallel_map_keeps_order() {
+        let items: Vec<usize> = (0..20).collect();
+        assert_eq!(parallel_map(&items, 3, |&x| x * x)[..4], [0, 1, 4, 9]);
+        assert_eq!(parallel_map(&items, 0, |&x| x).len(), 20);
+    }
+
+    #[test]
+    fn test_solve_enigma_invalid() {
+        let scorer = NGramScorer::english_quadgrams();
+        let options = EnigmaAttackOptions {
+            rotors: vec![Rotor::I, Rotor::II],
+            ..EnigmaAttackOptions::default()
+        };
+        assert!(solve_enigma("ABCDEFGH", &scorer, &options).is_err());
+        assert!(solve_enigma("A", &scorer, &EnigmaAttackOptions::default()).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use std::ops::ControlFlow;
+
+use crate::analysis::NGramScorer;
+use crate::classic::Substitution;
+use crate::cryptanalysis::Candidate;
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::KeyGenerator;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HillClimbOptions {
+    /// Climbs from fresh random keys; the best result wins.
+    pub restarts: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for HillClimbOptions {
+    fn default() -> Self {
+        HillClimbOptions {
+            restarts: 20,
+            seed: None,
+        }
+    }
+}
+
+/// Reported to the progress callback after every restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HillClimbProgress<'a> {
+    /// Restarts finished so far, counting this one.
+    pub restart: usize,
+    pub restarts: usize,
+    /// Score this restart converged to.
+    pub score: f64,
+    pub best_score: f64,
+    /// Best cipher alphabet so far, in the format of `Substitution::key`.
+    pub best_key: &'a str,
+}
+
+/// Recovers a substitution key from ciphertext alone by hill climbing on
+/// `scorer`, over the scorer's alphabet.
+///
+/// Keys are scored on the decrypted letters; higher is better.
+pub fn solve_substitution(
+    ciphertext: &str,
+    scorer: &NGramScorer,
+    options: &HillClimbOptions,
+) -> Result<Candidate<Substitution>, PolygraphiaError> {
+    solve_substitution_with_progress(ciphertext, scorer, options, |_| ControlFlow::Continue(()))
+}
+
+/// As `solve_substitution`, calling `progress` after every restart.
+///
+/// Returning `ControlFlow::Break` stops the search early; the best key found
+/// so far is still returned, with whatever plaintext it produces.
+pub fn solve_substitution_with_progress(
+    ciphertext: &str,
+    scorer: &NGramScorer,
+    options: &HillClimbOptions,
+    mut progress: impl FnMut(&HillClimbProgress) -> ControlFlow<()>,
+) -> Result<Candidate<Substitution>, PolygraphiaError> {
+    let alphabet = scorer.alphabet();
+    let letters: Vec<u8> = ciphertext
+        .chars()
+        .filter_map(|c| alphabet.index_of(c))
+        .map(|idx| idx as u8)
+        .collect();
+    if letters.len() < scorer.n() {
+        return Err(PolygraphiaError::InvalidInput(format!(
+            "Ciphertext must contain at least {} letters",
+            scorer.n()
+        )));
+    }
+
+    let mut generator = match options.seed {
+        Some(seed) => KeyGenerator::from_seed(seed),
+        None => KeyGenerator::new(),
+    };
+    let climb = Climb::new(letters, scorer);
+    let restarts = options.restarts.max(1);
+    let mut best: Option<(Vec<u8>, f64, String)> = None;
+    for restart in 1..=restarts {
+        let start: Vec<u8> = generator
+            .permutation(alphabet)
+            .chars()
+            .filter_map(|c| alphabet.index_of(c))
+            .map(|idx| idx as u8)
+            .collect();
+        let (decryption, score) = climb.run(start);
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_score, _)| score > *best_score)
+        {
+            let key = encryption_key(&decryption, scorer);
+            best = Some((decryption, score, key));
+        }
+
+        let (_, best_score, best_key) = best.as_ref().expect("just set");
+        let report = HillClimbProgress {
+            restart,
+            restarts,
+            score,
+            best_score: *best_score,
+            best_key,
+        };
+        if progress(&report).is_break() {
+            break;
+        }
+    }
+
+    let (_, score, key) = best.expect("at least one restart");
+    let key = Substitution::with_alphabet(&key, alphabet.clone())?;
+    Ok(Candidate {
+        plaintext: key.decrypt(ciphertext)?,
+        key,
+        score,
+    })
+}
+
+/// Cipher alphabet for a map from ciphertext to plaintext symbols.
+fn encryption_key(decryption: &[u8], scorer: &NGramScorer) -> String {
+    let mut forward = vec![0u8; decryption.len()];
+    for (cipher, &plain) in decryption.iter().enumerate() {
+        forward[plain as usize] = cipher as u8;
+    }
+    forward
+        .into_iter()
+        .map(|idx| scorer.alphabet().symbol(idx as usize))
+        .collect()
+}
+
+struct Climb<'a> {
+    letters: Vec<u8>,
+    /// Where each ciphertext symbol occurs.
+    occurrences: Vec<Vec<usize>>,
+    scorer: &'a NGramScorer,
+}
+
+impl<'a> Climb<'a> {
+    fn new(letters: Vec<u8>, scorer: &'a NGramScorer) -> Self {
+        let mut occurrences = vec![Vec::new(); scorer.alphabet().len()];
+        for (pos, &c) in letters.iter().enumerate() {
+            occurrences[c as usize].push(pos);
+        }
+        Climb {
+            letters,
+            occurrences,
+            scorer,
+        }
+    }
+
+    /// Swaps pairs of plaintext symbols, keeping any swap that helps, until
+    /// no single swap improves the score.
+    fn run(&self, mut decryption: Vec<u8>) -> (Vec<u8>, f64) {
+        let mut current: Vec<u8> = self
+            .letters
+            .iter()
+            .map(|&c| decryption[c as usize])
+            .collect();
+        let mut trial = current.clone();
+        let mut score = self.scorer.score_indices(&current);
+        let mut positions = Vec::new();
+
+        let size = decryption.len();
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for a in 0..size {
+                for b in a + 1..size {
+                    positions.clear();
+                    positions.extend_from_slice(&self.occurrences[a]);
+                    positions.extend_from_slice(&self.occurrences[b]);
+                    if positions.is_empty() {
+                        continue;
+                    }
+                    for &pos in &positions {
+                        trial[pos] = if self.letters[pos] as usize == a {
+                            decryption[b]
+                        } else {
+                            decryption[a]
+                        };
+                    }
+                    let trial_score = self.scorer.rescore(score, &current, &trial, &positions);
+                    if trial_score > score {
+                        decryption.swap(a, b);
+                        score = trial_score;
+                        improved = true;
+                        for &pos in &positions {
+                            current[pos] = trial[pos];
+                        }
+                    } else {
+                        for &pos in &positions {
+                            trial[pos] = current[pos];
+                        }
+                    }
+                }
+            }
+        }
+        (decryption, score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Alphabet;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age of \
+        wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of Light, it was the season of Darkness, it was the \
+        spring of hope, it was the winter of despair, we had everything before us, we had \
+        nothing before us, we were all going direct to Heaven, we were all going direct the \
+        other way.";
+
+    #[test]
+    fn test_solve_substitution() {
+        let cipher = Substitution::from_keyword("charles dickens").unwrap();
+        let ciphertext = cipher.encrypt(PLAINTEXT).unwrap();
+        let scorer = NGramScorer::english_quadgrams();
+        let options = HillClimbOptions {
+            seed: Some(1),
+            ..HillClimbOptions::default()
+        };
+
+        let solved = solve_substitution(&ciphertext, &scorer, &options).unwrap();
+        assert_eq!(solved.plaintext, PLAINTEXT);
+        assert_eq!(solved.key.encrypt(PLAINTEXT).unwrap(), ciphertext);
+        assert!(solved.score <= 0.0);
+    }
+
+    #[test]
+    fn test_progress_can_stop_early() {
+        let cipher =
+            Substitution::random_with(&mut KeyGenerator::from_seed(2), Alphabet::english())
+                .unwrap();
+        let ciphertext = cipher.encrypt(PLAINTEXT).unwrap();
+        let scorer = NGramScorer::english_quadgrams();
+        let options = HillClimbOptions {
+            restarts: 10,
+            seed: Some(2),
+        };
+
+        let mut reports = Vec::new();
+        let solved = solve_substitution_with_progress(&ciphertext, &scorer, &options, |p| {
+            reports.push((p.restart, p.restarts, p.best_score, p.best_key.to_string()));
+            if p.restart == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].1, 10);
+        assert!(reports[1].2 >= reports[0].2);
+        assert_eq!(solved.key.key(), reports[1].3);
+        assert_eq!(solved.score, reports[1].2);
+        assert_eq!(solved.plaintext.len(), PLAINTEXT.len());
+    }
+
+    #[test]
+    fn test_solve_substitution_rejects_short_input() {
+        let scorer = NGramScorer::english_quadgrams();
+        assert!(solve_substitution("abc", &scorer, &HillClimbOptions::default()).is_err());
+    }
+}
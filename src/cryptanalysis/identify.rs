@@ -0,0 +1,222 @@
+use crate::analysis::{self, ENGLISH_FREQUENCIES};
+use crate::cryptanalysis::friedman_key_lengths;
+use crate::utils::Alphabet;
+
+/// Longest period checked when looking for polyalphabetic structure.
+const MAX_PERIOD: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CipherFamily {
+    /// Letters rearranged but unchanged, e.g. rail fence or columnar.
+    Transposition,
+    /// One fixed letter-for-letter mapping, e.g. Caesar, Affine or a keyword alphabet.
+    MonoalphabeticSubstitution,
+    Playfair,
+    Hill,
+    /// Several alphabets used in turn, e.g. Vigenère or Beaufort.
+    Polyalphabetic,
+}
+
+/// Measurements of an English-alphabet ciphertext used by `identify_cipher`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextFeatures {
+    pub length: usize,
+    pub index_of_coincidence: f64,
+    /// Chi-squared against English letter frequencies, letter by letter.
+    pub chi_squared: f64,
+    /// Chi-squared after sorting both distributions, which ignores which letter
+    /// is which and only compares the shape.
+    pub sorted_chi_squared: f64,
+    /// Highest average column index of coincidence for periods 2 to 20.
+    pub periodic_index_of_coincidence: f64,
+    /// Pairs at positions (0, 1), (2, 3), ... made of the same letter twice.
+    pub doubled_digraphs: usize,
+    pub contains_j: bool,
+}
+
+impl TextFeatures {
+    pub fn from_text(ciphertext: &str) -> Self {
+        let alphabet = Alphabet::english();
+        let letters: Vec<usize> = ciphertext
+            .chars()
+            .filter_map(|c| alphabet.index_of(c))
+            .collect();
+        let counts = analysis::letter_counts(ciphertext, &alphabet);
+
+        let mut sorted_counts = counts.clone();
+        sorted_counts.sort_unstable_by(|a, b| b.cmp(a));
+        let mut sorted_expected = ENGLISH_FREQUENCIES;
+        sorted_expected.sort_unstable_by(|a, b| b.total_cmp(a));
+
+        let periodic_index_of_coincidence = friedman_key_lengths(ciphertext, &alphabet, MAX_PERIOD)
+            .into_iter()
+            .filter(|&(period, _)| period > 1)
+            .map(|(_, ioc)| ioc)
+            .fold(0.0, f64::max);
+
+        TextFeatures {
+            length: letters.len(),
+            index_of_coincidence: analysis::index_of_coincidence(ciphertext, &alphabet),
+            chi_squared: analysis::chi_squared(&counts, &ENGLISH_FREQUENCIES),
+            sorted_chi_squared: analysis::chi_squared(&sorted_counts, &sorted_expected),
+            periodic_index_of_coincidence,
+            doubled_digraphs: letters
+                .chunks_exact(2)
+                .filter(|pair| pair[0] == pair[1])
+                .count(),
+            contains_j: letters.contains(&9),
+        }
+    }
+}
+
+/// Estimates which cipher family produced an English ciphertext.
+///
+/// Returns every family with a probability, most likely first. The estimate
+/// needs a few hundred letters to be dependable.
+pub fn identify_cipher(ciphertext: &str) -> Vec<(CipherFamily, f64)> {
+    rank(&TextFeatures::from_text(ciphertext))
+}
+
+fn rank(features: &TextFeatures) -> Vec<(CipherFamily, f64)> {
+    let ioc = features.index_of_coincidence;
+    let per_letter = |chi: f64| chi / features.length.max(1) as f64;
+    let periodic_gain = features.periodic_index_of_coincidence - ioc;
+    let digraphic = features.length.is_multiple_of(2) && features.doubled_digraphs == 0;
+
+    // Log-likelihoods from rough Gaussian fits to each family's typical values
+    let log_likelihoods = [
+        (
+            CipherFamily::Transposition,
+            gaussian(ioc, 0.066, 0.008) + gaussian(per_letter(features.chi_squared), 0.0, 0.15),
+        ),
+        (
+            CipherFamily::MonoalphabeticSubstitution,
+            gaussian(ioc, 0.066, 0.008)
+                + gaussian(per_letter(features.sorted_chi_squared), 0.0, 0.15)
+                + if per_letter(features.chi_squared) > 0.5 {
+                    0.0
+                } else {
+                    -4.0
+                },
+        ),
+        (
+            CipherFamily::Playfair,
+            gaussian(ioc, 0.052, 0.007)
+                + if digraphic { 0.0 } else { -20.0 }
+                + if features.contains_j { -20.0 } else { 0.0 },
+        ),
+        (
+            CipherFamily::Hill,
+            gaussian(ioc, 0.041, 0.004) + gaussian(periodic_gain, 0.005, 0.005),
+        ),
+        (
+            CipherFamily::Polyalphabetic,
+            gaussian(ioc, 0.045, 0.006) + gaussian(periodic_gain, 0.03, 0.01),
+        ),
+    ];
+
+    let max = log_likelihoods
+        .iter()
+        .map(|&(_, l)| l)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<(CipherFamily, f64)> = log_likelihoods
+        .iter()
+        .map(|&(family, l)| (family, (l - max).exp()))
+        .collect();
+    let total: f64 = weights.iter().map(|&(_, w)| w).sum();
+    let mut ranking: Vec<(CipherFamily, f64)> = weights
+        .into_iter()
+        .map(|(family, w)| (family, w / total))
+        .collect();
+    ranking.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranking
+}
+
+/// Log of an unnormalised Gaussian density.
+fn gaussian(x: f64, mean: f64, std_dev: f64) -> f64 {
+    let z = (x - mean) / std_dev;
+    -0.5 * z * z
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classic::{Affine, Caesar, Hill, Playfair, Vigenere};
+    use crate::traits::Cipher;
+    use crate::utils::KeyGenerator;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age \
+        of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of Light, it was the season of Darkness, it was the \
+        spring of hope, it was the winter of despair, we had everything before us, we had \
+        nothing before us, we were all going direct to Heaven, we were all going direct the \
+        other way. In short, the period was so far like the present period, that some of its \
+        noisiest authorities insisted on its being received, for good or for evil, in the \
+        superlative degree of comparison only. There were a king with a large jaw and a queen \
+        with a plain face, on the throne of England; there were a king with a large jaw and \
+        a queen with a fair face, on the throne of France.";
+
+    fn columnar(text: &str, width: usize) -> String {
+        let letters: Vec<char> = text.chars().filter(|c| c.is_ascii_alphabetic()).collect();
+        (0..width)
+            .flat_map(|col| letters.iter().skip(col).step_by(width))
+            .collect()
+    }
+
+    fn top(ciphertext: &str) -> CipherFamily {
+        identify_cipher(ciphertext)[0].0
+    }
+
+    #[test]
+    fn test_identify_cipher() {
+        let hill =
+            Hill::random_invertible_with(3, &mut KeyGenerator::from_seed(3), Alphabet::english())
+                .unwrap();
+        let cases = [
+            (columnar(PLAINTEXT, 7), CipherFamily::Transposition),
+            (
+                Caesar::new(3).unwrap().encrypt(PLAINTEXT).unwrap(),
+                CipherFamily::MonoalphabeticSubstitution,
+            ),
+            (
+                Affine::new(7, 5).unwrap().encrypt(PLAINTEXT).unwrap(),
+                CipherFamily::MonoalphabeticSubstitution,
+            ),
+            (
+                Playfair::new("dickens")
+                    .unwrap()
+                    .encrypt(PLAINTEXT)
+                    .unwrap(),
+                CipherFamily::Playfair,
+            ),
+            (hill.encrypt(PLAINTEXT).unwrap(), CipherFamily::Hill),
+            (
+                Vigenere::new("lemon").unwrap().encrypt(PLAINTEXT).unwrap(),
+                CipherFamily::Polyalphabetic,
+            ),
+        ];
+        for (ciphertext, family) in cases {
+            assert_eq!(top(&ciphertext), family);
+        }
+    }
+
+    #[test]
+    fn test_probabilities_sum_to_one() {
+        for text in [PLAINTEXT, "", "xyz"] {
+            let ranking = identify_cipher(text);
+            assert_eq!(ranking.len(), 5);
+            let total: f64 = ranking.iter().map(|&(_, p)| p).sum();
+            assert!((total - 1.0).abs() < 1e-9);
+            assert!(ranking.windows(2).all(|w| w[0].1 >= w[1].1));
+        }
+    }
+
+    #[test]
+    fn test_text_features() {
+        let features = TextFeatures::from_text("Aa bc, dd!");
+        assert_eq!(features.length, 6);
+        assert_eq!(features.doubled_digraphs, 2);
+        assert!(!features.contains_j);
+        assert!(TextFeatures::from_text("jam").contains_j);
+    }
+}
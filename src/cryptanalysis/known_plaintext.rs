@@ -0,0 +1,177 @@
+use crate::classic::Hill;
+use crate::error::PolygraphiaError;
+use crate::utils::{Alphabet, Matrix};
+
+/// Upper bound on block combinations tried before giving up.
+const MAX_COMBINATIONS: usize = 100_000;
+
+/// Recovers a Hill key over the English alphabet from matching plaintext and ciphertext.
+pub fn known_plaintext_hill(
+    plaintext: &str,
+    ciphertext: &str,
+    block_size: usize,
+) -> Result<Hill, PolygraphiaError> {
+    known_plaintext_hill_with(plaintext, ciphertext, block_size, Alphabet::english())
+}
+
+/// Solves `C = K * P` for the key `K`, where the columns of `P` and `C` are
+/// `block_size` aligned plaintext and ciphertext blocks.
+///
+/// If the first blocks do not form a matrix invertible mod the alphabet size,
+/// other combinations of blocks are tried. The recovered key must reproduce
+/// every known block.
+pub fn known_plaintext_hill_with(
+    plaintext: &str,
+    ciphertext: &str,
+    block_size: usize,
+    alphabet: Alphabet,
+) -> Result<Hill, PolygraphiaError> {
+    if block_size == 0 {
+        return Err(PolygraphiaError::InvalidInput(
+            "Block size must be at least 1".to_string(),
+        ));
+    }
+    let modulus = alphabet.len() as u64;
+    let plain = indices(plaintext, &alphabet);
+    let cipher = indices(ciphertext, &alphabet);
+    let usable = plain.len().min(cipher.len()) / block_size * block_size;
+    let plain_blocks: Vec<&[u64]> = plain[..usable].chunks(block_size).collect();
+    let cipher_blocks: Vec<&[u64]> = cipher[..usable].chunks(block_size).collect();
+    if plain_blocks.len() < block_size {
+        return Err(PolygraphiaError::InvalidInput(format!(
+            "Need at least {} known letters for a {block_size}x{block_size} key, got {usable}",
+            block_size * block_size
+        )));
+    }
+
+    let mut combination: Vec<usize> = (0..block_size).collect();
+    for _ in 0..MAX_COMBINATIONS {
+        let p = columns(&plain_blocks, &combination)?;
+        if let Ok(p_inv) = p.mod_inverse(modulus) {
+            let c = columns(&cipher_blocks, &combination)?;
+            let key = c.multiply(&p_inv, modulus)?;
+            let reproduces_all = plain_blocks
+                .iter()
+                .zip(&cipher_blocks)
+                .all(|(p, c)| key.multiply_vector(p, modulus) == *c);
+            if !reproduces_all {
+                return Err(PolygraphiaError::InvalidInput(
+                    "Texts are not consistent with a Hill cipher of this block size".to_string(),
+                ));
+            }
+            return Hill::from_matrix(&key, alphabet);
+        }
+        if !next_combination(&mut combination, plain_blocks.len()) {
+            break;
+        }
+    }
+    Err(PolygraphiaError::InvalidInput(
+        "No combination of known plaintext blocks is invertible".to_string(),
+    ))
+}
+
+fn indices(text: &str, alphabet: &Alphabet) -> Vec<u64> {
+    text.chars()
+        .filter_map(|c| alphabet.index_of(c))
+        .map(|idx| idx as u64)
+        .collect()
+}
+
+fn columns(blocks: &[&[u64]], chosen: &[usize]) -> Result<Matrix, PolygraphiaError> {
+    let size = chosen.len();
+    let mut data = vec![0; size * size];
+    for (col, &block) in chosen.iter().enumerate() {
+        for row in 0..size {
+            data[row * size + col] = blocks[block][row];
+        }
+    }
+    Matrix::new(size, data)
+}
+
+/// Advances `combination` to the next k-subset of `0..n` in lexicographic order.
+fn next_combination(combination: &mut [usize], n: usize) -> bool {
+    let k = combination.len();
+    for i in (0..k).rev() {
+        if combination[i] < n - k + i {
+            combination[i] += 1;
+            for j in i + 1..k {
+                combination[j] = combination[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::Cipher;
+    use crate::utils::KeyGenerator;
+
+    #[test]
+    fn test_known_plaintext_2x2() {
+        let hill = Hill::new("hill").unwrap();
+        let plaintext = "short example";
+        let ciphertext = hill.encrypt(plaintext).unwrap();
+
+        let recovered = known_plaintext_hill(plaintext, &ciphertext, 2).unwrap();
+        assert_eq!(recovered.key(), hill.key());
+        assert_eq!(recovered.decrypt(&ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn test_known_plaintext_skips_singular_blocks() {
+        // "aa" and "bb" are dependent, so the solver must reach for a later block
+        let hill = Hill::new("ddcf").unwrap();
+        let plaintext = "aabbbbcdxy";
+        let ciphertext = hill.encrypt(plaintext).unwrap();
+
+        let recovered = known_plaintext_hill(plaintext, &ciphertext, 2).unwrap();
+        assert_eq!(recovered.key(), hill.key());
+    }
+
+    #[test]
+    fn test_known_plaintext_random_keys() {
+        let mut generator = KeyGenerator::from_seed(21);
+        let plaintext = "it was the best of times it was the worst of times \
+            it was the age of wisdom it was the age of foolishness";
+        for size in 2..=5 {
+            let hill =
+                Hill::random_invertible_with(size, &mut generator, Alphabet::english()).unwrap();
+            let ciphertext = hill.encrypt(plaintext).unwrap();
+            let recovered = known_plaintext_hill(plaintext, &ciphertext, size).unwrap();
+            assert_eq!(recovered.key(), hill.key());
+        }
+    }
+
+    #[test]
+    fn test_known_plaintext_errors() {
+        assert!(known_plaintext_hill("ab", "cd", 2).is_err());
+        assert!(known_plaintext_hill("abcd", "efgh", 0).is_err());
+        // Only repeated blocks are known
+        assert!(known_plaintext_hill("aaaaaaaa", "bbbbbbbb", 2).is_err());
+        // Inconsistent with any 2x2 key
+        assert!(known_plaintext_hill("abbaabbc", "abbaabbd", 2).is_err());
+    }
+
+    #[test]
+    fn test_next_combination() {
+        let mut combination = vec![0, 1];
+        let mut seen = vec![combination.clone()];
+        while next_combination(&mut combination, 4) {
+            seen.push(combination.clone());
+        }
+        assert_eq!(
+            seen,
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+    }
+}
@@ -0,0 +1,27 @@
+pub mod annealing;
+pub mod brute_force;
+pub mod enigma;
+pub mod hill_climbing;
+pub mod identify;
+pub mod known_plaintext;
+pub mod polyalphabetic;
+pub mod transposition;
+
+pub use annealing::*;
+pub use brute_force::*;
+pub use enigma::*;
+pub use hill_climbing::*;
+pub use identify::*;
+pub use known_plaintext::*;
+pub use polyalphabetic::*;
+pub use transposition::*;
+
+/// A key tried during an attack, with the plaintext it produces and its score.
+///
+/// Each attack documents whether lower or higher scores are better.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate<K> {
+    pub key: K,
+    pub plaintext: String,
+    pub score: f64,
+}
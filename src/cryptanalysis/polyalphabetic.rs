@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use crate::analysis;
+use crate::classic::{Vigenere, VigenereVariant};
+use crate::cryptanalysis::Candidate;
+use crate::cryptanalysis::brute_force::{brute_force_caesar_with, expected_frequencies};
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::Alphabet;
+
+/// Number of Friedman and Kasiski key lengths tried by `solve_vigenere`.
+const FRIEDMAN_CANDIDATES: usize = 3;
+const KASISKI_CANDIDATES: usize = 2;
+
+/// Kasiski examination: the share of spacings between repeated trigrams that
+/// each key length from 2 to `max_length` divides.
+///
+/// Sorted best first. Every divisor of the key length does at least as well as
+/// the key length itself, so ties favour the longer length.
+pub fn kasiski_examination(
+    ciphertext: &str,
+    alphabet: &Alphabet,
+    max_length: usize,
+) -> Vec<(usize, f64)> {
+    let letters = indices(ciphertext, alphabet);
+    let mut last_seen: HashMap<&[usize], usize> = HashMap::new();
+    let mut spacings = Vec::new();
+    for (pos, gram) in letters.windows(3).enumerate() {
+        if let Some(previous) = last_seen.insert(gram, pos) {
+            spacings.push(pos - previous);
+        }
+    }
+    let mut scores: Vec<(usize, f64)> = (2..=max_length)
+        .map(|length| {
+            let divisible = spacings.iter().filter(|&&s| s % length == 0).count();
+            let share = if spacings.is_empty() {
+                0.0
+            } else {
+                divisible as f64 / spacings.len() as f64
+            };
+            (length, share)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(b.0.cmp(&a.0)));
+    scores
+}
+
+/// Friedman column analysis: the average index of coincidence of the columns
+/// obtained by splitting the ciphertext with each key length from 1 to `max_length`.
+///
+/// Columns of the right length read like plain language, so their index of
+/// coincidence rises towards the language's value. Sorted highest first.
+pub fn friedman_key_lengths(
+    ciphertext: &str,
+    alphabet: &Alphabet,
+    max_length: usize,
+) -> Vec<(usize, f64)> {
+    let letters = indices(ciphertext, alphabet);
+    let mut scores: Vec<(usize, f64)> = (1..=max_length.min(letters.len() / 2).max(1))
+        .map(|length| {
+            let total: f64 = columns(&letters, length, alphabet)
+                .iter()
+                .map(|column| analysis::index_of_coincidence(column, alphabet))
+                .sum();
+            (length, total / length as f64)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    scores
+}
+
+/// Breaks a Vigenère ciphertext over the English alphabet.
+///
+/// Candidates are sorted by chi-squared score, lowest (most English-like) first.
+pub fn solve_vigenere(
+    ciphertext: &str,
+    max_key_length: usize,
+) -> Result<Vec<Candidate<String>>, PolygraphiaError> {
+    solve_vigenere_with(ciphertext, &Alphabet::english(), max_key_length)
+}
+
+/// Estimates likely key lengths with Friedman and Kasiski analysis, then solves
+/// each column as a Caesar cipher.
+pub fn solve_vigenere_with(
+    ciphertext: &str,
+    alphabet: &Alphabet,
+    max_key_length: usize,
+) -> Result<Vec<Candidate<String>>, PolygraphiaError> {
+    let expected = expected_frequencies(alphabet)?;
+    let letters = indices(ciphertext, alphabet);
+    if letters.len() < 2 {
+        return Err(PolygraphiaError::InvalidInput(
+            "Ciphertext must contain at least two letters".to_string(),
+        ));
+    }
+
+    let mut lengths: Vec<usize> = friedman_key_lengths(ciphertext, alphabet, max_key_length)
+        .into_iter()
+        .take(FRIEDMAN_CANDIDATES)
+        .map(|(length, _)| length)
+        .collect();
+    lengths.extend(
+        kasiski_examination(ciphertext, alphabet, max_key_length)
+            .into_iter()
+            .filter(|&(_, share)| share > 0.0)
+            .take(KASISKI_CANDIDATES)
+            .map(|(length, _)| length),
+    );
+
+    let mut candidates: Vec<Candidate<String>> = Vec::new();
+    for length in lengths {
+        let mut key = String::with_capacity(length);
+        for column in columns(&letters, length, alphabet) {
+            let best = brute_force_caesar_with(&column, alphabet)?;
+            key.push(alphabet.symbol(best[0].key as usize));
+        }
+        let key = shortest_period(&key);
+        if candidates.iter().any(|c| c.key == key) {
+            continue;
+        }
+        let cipher = Vigenere::with_alphabet(&key, VigenereVariant::Vigenere, alphabet.clone())?;
+        let plaintext = cipher.decrypt(ciphertext)?;
+        let counts = analysis::letter_counts(&plaintext, alphabet);
+        candidates.push(Candidate {
+            key,
+            score: analysis::chi_squared(&counts, expected),
+            plaintext,
+        });
+    }
+    candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+    Ok(candidates)
+}
+
+fn indices(text: &str, alphabet: &Alphabet) -> Vec<usize> {
+    text.chars().filter_map(|c| alphabet.index_of(c)).collect()
+}
+
+fn columns(letters: &[usize], length: usize, alphabet: &Alphabet) -> Vec<String> {
+    (0..length)
+        .map(|start| {
+            letters
+                .iter()
+                .skip(start)
+                .step_by(length)
+                .map(|&idx| alphabet.symbol(idx))
+                .collect()
+        })
+        .collect()
+}
+
+/// "abcabc" -> "abc", so a key found at a multiple of its length is reported once.
+fn shortest_period(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let period = (1..=chars.len())
+        .find(|&p| {
+            chars.len().is_multiple_of(p)
+                && chars
+                    .iter()
+                    .zip(chars.iter().cycle().skip(p))
+                    .all(|(a, b)| a == b)
+        })
+        .unwrap_or(chars.len());
+    chars[..period].iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age \
+        of wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of Light, it was the season of Darkness, it was the \
+        spring of hope, it was the winter of despair, we had everything before us, we had \
+        nothing before us, we were all going direct to Heaven, we were all going direct the \
+        other way.";
+
+    #[test]
+    fn test_solve_vigenere() {
+        for key in ["lemon", "dickens", "cryptography"] {
+            let ciphertext = Vigenere::new(key).unwrap().encrypt(PLAINTEXT).unwrap();
+            let candidates = solve_vigenere(&ciphertext, 15).unwrap();
+            assert_eq!(candidates[0].key, key);
+            assert_eq!(candidates[0].plaintext, PLAINTEXT);
+            assert!(candidates.windows(2).all(|w| w[0].score <= w[1].score));
+        }
+    }
+
+    #[test]
+    fn test_friedman_key_lengths() {
+        let ciphertext = Vigenere::new("dickens")
+            .unwrap()
+            .encrypt(PLAINTEXT)
+            .unwrap();
+        let lengths = friedman_key_lengths(&ciphertext, &Alphabet::english(), 20);
+        assert_eq!(lengths[0].0 % 7, 0);
+        assert!(lengths.windows(2).all(|w| w[0].1 >= w[1].1));
+    }
+
+    #[test]
+    fn test_kasiski_examination() {
+        let ciphertext = Vigenere::new("lemon").unwrap().encrypt(PLAINTEXT).unwrap();
+        let lengths = kasiski_examination(&ciphertext, &Alphabet::english(), 12);
+        assert_eq!(lengths[0].0, 5);
+        assert_eq!(lengths.len(), 11);
+
+        let lengths = kasiski_examination("abcdefgh", &Alphabet::english(), 4);
+        assert!(lengths.iter().all(|&(_, share)| share == 0.0));
+    }
+
+    #[test]
+    fn test_shortest_period() {
+        assert_eq!(shortest_period("lemonlemon"), "lemon");
+        assert_eq!(shortest_period("aaaa"), "a");
+        assert_eq!(shortest_period("abcab"), "abcab");
+    }
+
+    #[test]
+    fn test_solve_vigenere_short_input() {
+        assert!(solve_vigenere("a", 5).is_err());
+    }
+}
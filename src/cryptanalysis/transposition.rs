@@ -0,0 +1,369 @@
+use rand::seq::SliceRandom;
+
+use crate::analysis::NGramScorer;
+use crate::classic::ColumnarTransposition;
+use crate::cryptanalysis::Candidate;
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+use crate::utils::KeyGenerator;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranspositionOptions {
+    /// Key lengths tried, for each transposition.
+    pub min_width: usize,
+    pub max_width: usize,
+    /// Width combinations with at most this many possible keys are searched
+    /// exhaustively; larger ones by hill climbing.
+    pub exhaustive_limit: usize,
+    /// Hill-climbing runs from fresh random keys per width combination.
+    pub restarts: usize,
+    pub seed: Option<u64>,
+}
+
+impl Default for TranspositionOptions {
+    fn default() -> Self {
+        TranspositionOptions {
+            min_width: 2,
+            max_width: 10,
+            exhaustive_limit: 40_320,
+            restarts: 50,
+            seed: None,
+        }
+    }
+}
+
+/// Recovers the column order of a single columnar transposition from
+/// ciphertext alone, over the scorer's alphabet.
+///
+/// Keys are scored by `scorer` on the decrypted letters; higher is better.
+/// Works with complete or incomplete columns; padding is left in the plaintext.
+pub fn solve_columnar(
+    ciphertext: &str,
+    scorer: &NGramScorer,
+    options: &TranspositionOptions,
+) -> Result<Candidate<ColumnarTransposition>, PolygraphiaError> {
+    solve(ciphertext, scorer, options, 1)
+}
+
+/// As `solve_columnar`, for two transpositions in a row. Every pair of widths
+/// is searched, so narrowing `min_width..=max_width` saves a lot of time.
+pub fn solve_double_columnar(
+    ciphertext: &str,
+    scorer: &NGramScorer,
+    options: &TranspositionOptions,
+) -> Result<Candidate<ColumnarTransposition>, PolygraphiaError> {
+    solve(ciphertext, scorer, options, 2)
+}
+
+fn solve(
+    ciphertext: &str,
+    scorer: &NGramScorer,
+    options: &TranspositionOptions,
+    passes: usize,
+) -> Result<Candidate<ColumnarTransposition>, PolygraphiaError> {
+    let alphabet = scorer.alphabet();
+    let letters: Vec<u8> = ciphertext
+        .chars()
+        .filter_map(|c| alphabet.index_of(c))
+        .map(|idx| idx as u8)
+        .collect();
+    let max_width = options.max_width.min(letters.len()).min(alphabet.len());
+    let min_width = options.min_width.max(2);
+    if min_width > max_width {
+        return Err(PolygraphiaError::InvalidInput(format!(
+            "No key width between {} and {} fits a ciphertext of {} letters",
+            options.min_width,
+            options.max_width,
+            letters.len()
+        )));
+    }
+
+    let mut generator = match options.seed {
+        Some(seed) => KeyGenerator::from_seed(seed),
+        None => KeyGenerator::new(),
+    };
+    let mut search = Search {
+        letters,
+        scorer,
+        work: Vec::new(),
+        plaintext: Vec::new(),
+    };
+
+    let mut best: Option<(Vec<Vec<usize>>, f64)> = None;
+    let mut widths = vec![min_width; passes];
+    loop {
+        let result = if key_count(&widths).is_some_and(|count| count <= options.exhaustive_limit) {
+            search.exhaustive(&widths)
+        } else {
+            search.climb(&widths, options.restarts.max(1), &mut generator)
+        };
+        if best
+            .as_ref()
+            .is_none_or(|(_, best_score)| result.1 > *best_score)
+        {
+            best = Some(result);
+        }
+        if !advance(&mut widths, min_width, max_width) {
+            break;
+        }
+    }
+
+    let (sequences, score) = best.expect("at least one width");
+    let orders: Vec<Vec<usize>> = sequences.iter().map(|seq| ranks(seq)).collect();
+    let key = ColumnarTransposition::from_orders(&orders, alphabet.clone())?;
+    Ok(Candidate {
+        plaintext: key.decrypt(ciphertext)?,
+        key,
+        score,
+    })
+}
+
+/// Number of keys for these widths, or `None` if it overflows.
+fn key_count(widths: &[usize]) -> Option<usize> {
+    widths.iter().try_fold(1usize, |count, &width| {
+        (2..=width).try_fold(count, |acc, n| acc.checked_mul(n))
+    })
+}
+
+/// Steps through every combination of widths, like an odometer.
+fn advance(widths: &mut [usize], min_width: usize, max_width: usize) -> bool {
+    for width in widths.iter_mut().rev() {
+        if *width < max_width {
+            *width += 1;
+            return true;
+        }
+        *width = min_width;
+    }
+    false
+}
+
+/// Rank of each column from the order the columns are read in.
+fn ranks(sequence: &[usize]) -> Vec<usize> {
+    let mut order = vec![0; sequence.len()];
+    for (rank, &col) in sequence.iter().enumerate() {
+        order[col] = rank;
+    }
+    order
+}
+
+/// Rearranges `sequence` into the next permutation in lexicographic order,
+/// returning false (and the first permutation) after the last one.
+fn next_permutation(sequence: &mut [usize]) -> bool {
+    let Some(pivot) = sequence.windows(2).rposition(|w| w[0] < w[1]) else {
+        sequence.reverse();
+        return false;
+    };
+    let successor = sequence
+        .iter()
+        .rposition(|&x| x > sequence[pivot])
+        .expect("pivot has a larger element after it");
+    sequence.swap(pivot, successor);
+    sequence[pivot + 1..].reverse();
+    true
+}
+
+/// Keys are held as the order columns are read in, one sequence per pass.
+struct Search<'a> {
+    letters: Vec<u8>,
+    scorer: &'a NGramScorer,
+    work: Vec<u8>,
+    plaintext: Vec<u8>,
+}
+
+impl Search<'_> {
+    fn score(&mut self, sequences: &[Vec<usize>]) -> f64 {
+        self.plaintext.clone_from(&self.letters);
+        let length = self.letters.len();
+        for sequence in sequences.iter().rev() {
+            std::mem::swap(&mut self.work, &mut self.plaintext);
+            self.plaintext.resize(length, 0);
+            let width = sequence.len();
+            let sources = sequence
+                .iter()
+                .flat_map(|&col| (col..length).step_by(width));
+            for (&letter, to) in self.work.iter().zip(sources) {
+                self.plaintext[to] = letter;
+            }
+        }
+        self.scorer.score_indices(&self.plaintext)
+    }
+
+    fn exhaustive(&mut self, widths: &[usize]) -> (Vec<Vec<usize>>, f64) {
+        let mut sequences: Vec<Vec<usize>> = widths.iter().map(|&w| (0..w).collect()).collect();
+        let mut best = (sequences.clone(), self.score(&sequences));
+        loop {
+            let mut advanced = false;
+            for sequence in sequences.iter_mut().rev() {
+                if next_permutation(sequence) {
+                    advanced = true;
+                    break;
+                }
+            }
+            if !advanced {
+                return best;
+            }
+            let score = self.score(&sequences);
+            if score > best.1 {
+                best = (sequences.clone(), score);
+            }
+        }
+    }
+
+    fn climb(
+        &mut self,
+        widths: &[usize],
+        restarts: usize,
+        generator: &mut KeyGenerator,
+    ) -> (Vec<Vec<usize>>, f64) {
+        let mut best: Option<(Vec<Vec<usize>>, f64)> = None;
+        for _ in 0..restarts {
+            let mut sequences: Vec<Vec<usize>> = widths
+                .iter()
+                .map(|&w| {
+                    let mut sequence: Vec<usize> = (0..w).collect();
+                    sequence.shuffle(generator.rng());
+                    sequence
+                })
+                .collect();
+            let score = self.climb_from(&mut sequences);
+            if best
+                .as_ref()
+                .is_none_or(|(_, best_score)| score > *best_score)
+            {
+                best = Some((sequences, score));
+            }
+        }
+        best.expect("at least one restart")
+    }
+
+    /// Applies column swaps and moves of runs of adjacent columns while any of
+    /// them helps. Moving whole runs keeps columns that already read well together.
+    fn climb_from(&mut self, sequences: &mut [Vec<usize>]) -> f64 {
+        let mut score = self.score(sequences);
+        let mut improved = true;
+        while improved {
+            improved = false;
+            for pass in 0..sequences.len() {
+                for neighbour in neighbours(&sequences[pass]) {
+                    let before = std::mem::replace(&mut sequences[pass], neighbour);
+                    let candidate = self.score(sequences);
+                    if candidate > score {
+                        score = candidate;
+                        improved = true;
+                    } else {
+                        sequences[pass] = before;
+                    }
+                }
+            }
+        }
+        score
+    }
+}
+
+/// Every sequence one swap or one run move away from `sequence`.
+fn neighbours(sequence: &[usize]) -> Vec<Vec<usize>> {
+    let width = sequence.len();
+    let mut result = Vec::new();
+    for i in 0..width {
+        for j in i + 1..width {
+            let mut swapped = sequence.to_vec();
+            swapped.swap(i, j);
+            result.push(swapped);
+        }
+    }
+    for start in 0..width {
+        for end in start + 1..=width {
+            let run = &sequence[start..end];
+            let rest: Vec<usize> = [&sequence[..start], &sequence[end..]].concat();
+            for at in 0..=rest.len() {
+                if at != start {
+                    result.push([&rest[..at], run, &rest[at..]].concat());
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::TextMode;
+
+    const PLAINTEXT: &str = "It was the best of times, it was the worst of times, it was the age of \
+        wisdom, it was the age of foolishness, it was the epoch of belief, it was the epoch \
+        of incredulity, it was the season of Light, it was the season of Darkness, it was the \
+        spring of hope, it was the winter of despair, we had everything before us, we had \
+        nothing before us, we were all going direct to Heaven, we were all going direct the \
+        other way.";
+
+    #[test]
+    fn test_solve_columnar() {
+        let scorer = NGramScorer::english_quadgrams();
+        // "zebras" is searched exhaustively, "lighthouse" by hill climbing
+        for key in ["zebras", "lighthouse"] {
+            let options = TranspositionOptions {
+                min_width: key.len() - 1,
+                max_width: key.len(),
+                restarts: 20,
+                seed: Some(1),
+                ..TranspositionOptions::default()
+            };
+            let cipher = ColumnarTransposition::new(key).unwrap();
+            let ciphertext = cipher.encrypt(PLAINTEXT).unwrap();
+            let solved = solve_columnar(&ciphertext, &scorer, &options).unwrap();
+            assert_eq!(solved.plaintext, PLAINTEXT);
+            assert_eq!(solved.key.column_orders(), cipher.column_orders());
+        }
+    }
+
+    #[test]
+    fn test_solve_double_columnar() {
+        let mut cipher = ColumnarTransposition::double("lamp", "tiger").unwrap();
+        cipher.set_mode(TextMode::AlphaOnly);
+        let ciphertext = cipher.encrypt(PLAINTEXT).unwrap();
+        let scorer = NGramScorer::english_quadgrams();
+        let options = TranspositionOptions {
+            min_width: 4,
+            max_width: 5,
+            seed: Some(1),
+            ..TranspositionOptions::default()
+        };
+
+        let solved = solve_double_columnar(&ciphertext, &scorer, &options).unwrap();
+        assert_eq!(solved.key.column_orders().len(), 2);
+        assert_eq!(
+            solved.plaintext,
+            cipher.decrypt(&ciphertext).unwrap(),
+            "recovered keys should be equivalent to the originals"
+        );
+    }
+
+    #[test]
+    fn test_next_permutation() {
+        let mut sequence = vec![0, 1, 2];
+        let mut seen = vec![sequence.clone()];
+        while next_permutation(&mut sequence) {
+            seen.push(sequence.clone());
+        }
+        assert_eq!(seen.len(), 6);
+        assert_eq!(seen[1], [0, 2, 1]);
+        assert_eq!(sequence, [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_key_count() {
+        assert_eq!(key_count(&[4, 5]), Some(24 * 120));
+        assert_eq!(key_count(&[30]), None);
+    }
+
+    #[test]
+    fn test_solve_columnar_invalid_widths() {
+        let scorer = NGramScorer::english_quadgrams();
+        let options = TranspositionOptions {
+            min_width: 8,
+            ..TranspositionOptions::default()
+        };
+        assert!(solve_columnar("abcde", &scorer, &options).is_err());
+    }
+}
@@ -1,13 +1,31 @@
+pub mod analysis;
 pub mod classic;
+pub mod cryptanalysis;
 pub mod error;
+pub mod stream;
 pub mod traits;
 pub mod utils;
 mod ffi;
 
+pub use classic::Adfgvx;
 pub use classic::Affine;
+pub use classic::Bifid;
 pub use classic::Caesar;
+pub use classic::ColumnarTransposition;
+pub use classic::Enigma;
+pub use classic::FourSquare;
 pub use classic::Hill;
 pub use classic::Playfair;
+pub use classic::RailFence;
+pub use classic::Substitution;
+pub use classic::Trifid;
+pub use classic::TwoSquare;
+pub use classic::Vigenere;
 pub use error::PolygraphiaError;
+pub use stream::{DecryptReader, EncryptWriter};
+pub use traits::ByteCipher;
 pub use traits::Cipher;
+pub use traits::StreamCipher;
+pub use utils::Alphabet;
+pub use utils::KeyGenerator;
 pub use utils::TextMode;
@@ -0,0 +1,443 @@
+use std::io::{self, Read, Write};
+
+use crate::error::PolygraphiaError;
+use crate::traits::StreamCipher;
+
+/// Stream state of ciphers that work on blocks of letters, or on single
+/// letters, and carry nothing else from one piece to the next.
+#[derive(Debug, Default, Clone)]
+pub struct BlockState {
+    /// Bytes of the pending text already scanned.
+    scanned: usize,
+    /// Letters among them.
+    letters: usize,
+    /// Start of the last block begun, and the letters before it.
+    cut: usize,
+    cut_letters: usize,
+}
+
+impl BlockState {
+    /// Byte offset in `text` that leaves a whole number of `block`-letter
+    /// blocks before it and at least one letter after it. `text` must be what
+    /// the previous cut left, with the next piece appended, so every byte is
+    /// only scanned once however long the text waits.
+    pub(crate) fn cut(
+        &mut self,
+        text: &str,
+        block: usize,
+        is_letter: impl Fn(char) -> bool,
+    ) -> usize {
+        for (i, c) in text[self.scanned..].char_indices() {
+            if is_letter(c) {
+                if self.letters.is_multiple_of(block) {
+                    self.cut = self.scanned + i;
+                    self.cut_letters = self.letters;
+                }
+                self.letters += 1;
+            }
+        }
+        let cut = self.cut;
+        self.scanned = text.len() - cut;
+        self.letters -= self.cut_letters;
+        self.cut = 0;
+        self.cut_letters = 0;
+        cut
+    }
+}
+
+/// Text received so far that has not been handed to the cipher yet.
+#[derive(Debug, Default)]
+struct Pending {
+    text: String,
+    /// The start of a character split across two buffers.
+    incomplete: Vec<u8>,
+}
+
+impl Pending {
+    fn push(&mut self, mut buf: &[u8]) -> io::Result<()> {
+        while !self.incomplete.is_empty() && !buf.is_empty() {
+            self.incomplete.push(buf[0]);
+            buf = &buf[1..];
+            match std::str::from_utf8(&self.incomplete) {
+                Ok(c) => {
+                    self.text.push_str(c);
+                    self.incomplete.clear();
+                }
+                Err(e) if e.error_len().is_some() => return Err(invalid_utf8()),
+                Err(_) => {}
+            }
+        }
+        match std::str::from_utf8(buf) {
+            Ok(text) => self.text.push_str(text),
+            Err(e) if e.error_len().is_none() => {
+                let (valid, rest) = buf.split_at(e.valid_up_to());
+                self.text
+                    .push_str(std::str::from_utf8(valid).map_err(|_| invalid_utf8())?);
+                self.incomplete.extend_from_slice(rest);
+            }
+            Err(_) => return Err(invalid_utf8()),
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> io::Result<()> {
+        if self.incomplete.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_utf8())
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "stream is not valid UTF-8")
+}
+
+fn cipher_error(e: PolygraphiaError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Encrypts UTF-8 text written to it and writes the ciphertext to `W`.
+///
+/// The output is the same as encrypting everything in one call: incomplete
+/// blocks, digraphs and characters wait for the next write, and padding is
+/// only added by `finish`, which should be called once the message is
+/// complete. Dropping the writer unfinished finishes it too, as `BufWriter`
+/// flushes on drop, but any error is lost.
+#[derive(Debug)]
+pub struct EncryptWriter<W: Write, C: StreamCipher> {
+    /// Only taken by `finish`.
+    inner: Option<W>,
+    cipher: C,
+    pending: Pending,
+    state: C::State,
+    out: String,
+    finished: bool,
+}
+
+impl<W: Write, C: StreamCipher> EncryptWriter<W, C> {
+    pub fn new(inner: W, cipher: C) -> Self {
+        EncryptWriter {
+            inner: Some(inner),
+            cipher,
+            pending: Pending::default(),
+            state: C::State::default(),
+            out: String::new(),
+            finished: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().expect("writer is only taken by finish")
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    /// Encrypts what is left of the message, padding it, and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.finish_message()?;
+        Ok(self.inner.take().expect("writer is only taken by finish"))
+    }
+
+    fn finish_message(&mut self) -> io::Result<()> {
+        self.finished = true;
+        let inner = self.inner.as_mut().expect("writer is only taken by finish");
+        self.pending.finish()?;
+        if !self.pending.text.is_empty() {
+            self.out.clear();
+            self.cipher
+                .encrypt_final(&self.pending.text, &mut self.state, &mut self.out)
+                .map_err(cipher_error)?;
+            inner.write_all(self.out.as_bytes())?;
+        }
+        inner.flush()
+    }
+}
+
+impl<W: Write, C: StreamCipher> Write for EncryptWriter<W, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = self.inner.as_mut().expect("writer is only taken by finish");
+        self.pending.push(buf)?;
+        self.out.clear();
+        let used = self
+            .cipher
+            .encrypt_partial(&self.pending.text, &mut self.state, &mut self.out)
+            .map_err(cipher_error)?;
+        self.pending.text.drain(..used);
+        inner.write_all(self.out.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    /// Flushes the underlying writer. Text that is still waiting for the rest
+    /// of its block stays buffered.
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner
+            .as_mut()
+            .expect("writer is only taken by finish")
+            .flush()
+    }
+}
+
+impl<W: Write, C: StreamCipher> Drop for EncryptWriter<W, C> {
+    fn drop(&mut self) {
+        if !self.finished && !std::thread::panicking() {
+            let _ = self.finish_message();
+        }
+    }
+}
+
+/// Decrypts UTF-8 ciphertext read from `R`, yielding the plaintext.
+///
+/// The output is the same as decrypting everything in one call; padding is
+/// only removed once `R` reaches its end.
+#[derive(Debug)]
+pub struct DecryptReader<R: Read, C: StreamCipher> {
+    inner: R,
+    cipher: C,
+    pending: Pending,
+    state: C::State,
+    buffer: Vec<u8>,
+    out: String,
+    position: usize,
+    done: bool,
+}
+
+impl<R: Read, C: StreamCipher> DecryptReader<R, C> {
+    pub fn new(inner: R, cipher: C) -> Self {
+        DecryptReader {
+            inner,
+            cipher,
+            pending: Pending::default(),
+            state: C::State::default(),
+            buffer: vec![0; 8192],
+            out: String::new(),
+            position: 0,
+            done: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn cipher(&self) -> &C {
+        &self.cipher
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        self.out.clear();
+        self.position = 0;
+        let n = self.inner.read(&mut self.buffer)?;
+        if n == 0 {
+            self.done = true;
+            self.pending.finish()?;
+            if !self.pending.text.is_empty() {
+                self.cipher
+                    .decrypt_final(&self.pending.text, &mut self.state, &mut self.out)
+                    .map_err(cipher_error)?;
+                self.pending.text.clear();
+            }
+            return Ok(());
+        }
+        self.pending.push(&self.buffer[..n])?;
+        let used = self
+            .cipher
+            .decrypt_partial(&self.pending.text, &mut self.state, &mut self.out)
+            .map_err(cipher_error)?;
+        self.pending.text.drain(..used);
+        Ok(())
+    }
+}
+
+impl<R: Read, C: StreamCipher> Read for DecryptReader<R, C> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.out.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+        let available = &self.out.as_bytes()[self.position..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classic::{
+        Caesar, Enigma, FourSquare, Hill, Playfair, Reflector, Rotor, TwoSquare, Vigenere,
+    };
+    use crate::traits::Cipher;
+    use crate::utils::padding::{Padding, Unpadding};
+    use crate::utils::{DoubledLetters, TextMode};
+
+    const TEXT: &str = "It was the best of times, it was the worst of times; \
+        it was the age of wisdom, it was the age of foolishness. Æsop's \
+        balloon fell off a tall hill — see you at noon, Mississippi!";
+
+    /// Writes `text` `size` bytes at a time, splitting characters too.
+    fn encrypt_in_pieces<C: StreamCipher>(cipher: C, text: &str, size: usize) -> String {
+        let mut writer = EncryptWriter::new(Vec::new(), cipher);
+        for piece in text.as_bytes().chunks(size) {
+            writer.write_all(piece).unwrap();
+        }
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    /// Reads `ciphertext` through a reader that hands out `size` bytes at a time.
+    fn decrypt_in_pieces<C: StreamCipher>(cipher: C, ciphertext: &str, size: usize) -> String {
+        struct Trickle<'a>(&'a [u8], usize);
+
+        impl Read for Trickle<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = self.0.len().min(self.1).min(buf.len());
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let mut plaintext = String::new();
+        DecryptReader::new(Trickle(ciphertext.as_bytes(), size), cipher)
+            .read_to_string(&mut plaintext)
+            .unwrap();
+        plaintext
+    }
+
+    fn assert_streams<C: StreamCipher + Clone>(cipher: C) {
+        assert_streams_text(cipher, TEXT, &[1, 2, 3, 5, 7, 64, 4096]);
+    }
+
+    fn assert_streams_text<C: StreamCipher + Clone>(cipher: C, text: &str, sizes: &[usize]) {
+        let expected = cipher.encrypt(text).unwrap();
+        let decrypted = cipher.decrypt(&expected).unwrap();
+        for &size in sizes {
+            let ciphertext = encrypt_in_pieces(cipher.clone(), text, size);
+            assert_eq!(ciphertext, expected, "{} in {size}s", cipher.name());
+            let plaintext = decrypt_in_pieces(cipher.clone(), &ciphertext, size);
+            assert_eq!(plaintext, decrypted, "{} in {size}s", cipher.name());
+        }
+    }
+
+    #[test]
+    fn test_stream_matches_whole_message() {
+        assert_streams(Caesar::new(7).unwrap());
+        assert_streams(Vigenere::new("lemon").unwrap());
+        assert_streams(Vigenere::autokey("queen").unwrap());
+        assert_streams(Enigma::new([Rotor::I, Rotor::II, Rotor::III], Reflector::B).unwrap());
+
+        let mut hill = Hill::new("gybnqkurp").unwrap();
+        assert_streams(hill.clone());
+        hill.set_padding(Padding::LengthMarker);
+        hill.set_unpadding(Unpadding::Exact);
+        hill.set_mode(TextMode::AlphaOnly);
+        assert_streams(hill);
+    }
+
+    #[test]
+    fn test_stream_playfair() {
+        let mut playfair = Playfair::new("playfair example").unwrap();
+        assert_streams(playfair.clone());
+        playfair.set_unpadding(Unpadding::Heuristic);
+        assert_streams(playfair.clone());
+        playfair.set_padding(Padding::LengthMarker);
+        playfair.set_unpadding(Unpadding::Exact);
+        playfair.set_filler('q').unwrap();
+        assert_streams(playfair.clone());
+        playfair.set_doubled_letters(DoubledLetters::Keep);
+        playfair.set_mode(TextMode::AlphaOnly);
+        assert_streams(playfair);
+    }
+
+    #[test]
+    fn test_stream_writes_before_finish() {
+        fn written_early<C: StreamCipher>(cipher: C) -> usize {
+            let mut writer = EncryptWriter::new(Vec::new(), cipher);
+            for piece in TEXT.as_bytes().chunks(5) {
+                writer.write_all(piece).unwrap();
+            }
+            writer.get_ref().len()
+        }
+
+        let four_square = FourSquare::new("zebras", "dingo").unwrap();
+        let two_square = TwoSquare::vertical("zebras", "dingo").unwrap();
+        assert_streams(four_square.clone());
+        assert_streams(two_square.clone());
+        assert!(written_early(four_square) > TEXT.len() - 10);
+        assert!(written_early(two_square) > TEXT.len() - 10);
+        assert!(written_early(Playfair::new("secret").unwrap()) > TEXT.len() - 10);
+        assert!(written_early(Hill::new("gybnqkurp").unwrap()) > TEXT.len() - 10);
+        assert!(written_early(Caesar::new(3).unwrap()) > TEXT.len() - 10);
+        assert!(written_early(Vigenere::autokey("queen").unwrap()) > TEXT.len() - 10);
+        let enigma = Enigma::new([Rotor::I, Rotor::II, Rotor::III], Reflector::B).unwrap();
+        assert!(written_early(enigma) > TEXT.len() - 10);
+    }
+
+    #[test]
+    fn test_stream_long_runs() {
+        // One-byte writes would rescan a waiting run every time if it were
+        // not remembered how far it had been scanned
+        let dots = format!("Go{}on", ".".repeat(50_000));
+        let doubled = format!("Baa{}h", "a".repeat(10_000));
+        for text in [&dots, &doubled] {
+            assert_streams_text(Caesar::new(3).unwrap(), text, &[1, 4096]);
+            assert_streams_text(Hill::new("gybnqkurp").unwrap(), text, &[1, 4096]);
+            assert_streams_text(Playfair::new("secret").unwrap(), text, &[1, 4096]);
+        }
+    }
+
+    #[test]
+    fn test_stream_finishes_on_drop() {
+        let mut ciphertext = Vec::new();
+        {
+            let mut writer = EncryptWriter::new(&mut ciphertext, Hill::new("hill").unwrap());
+            writer.write_all(b"short example").unwrap();
+        }
+        let expected = Hill::new("hill").unwrap().encrypt("short example").unwrap();
+        assert_eq!(String::from_utf8(ciphertext).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_stream_edge_cases() {
+        let cipher = Hill::new("hill").unwrap();
+        assert_eq!(encrypt_in_pieces(cipher.clone(), "", 4), "");
+
+        let mut writer = EncryptWriter::new(Vec::new(), cipher.clone());
+        writer.write_all("caf\u{e9}".as_bytes()).unwrap();
+        writer.write_all(&[0xc3]).unwrap();
+        assert!(writer.finish().is_err());
+
+        let mut writer = EncryptWriter::new(Vec::new(), cipher.clone());
+        assert!(writer.write_all(&[b'a', 0xff, b'b']).is_err());
+
+        let mut writer = EncryptWriter::new(Vec::new(), cipher);
+        writer.write_all(b"1234").unwrap();
+        assert!(writer.finish().is_err());
+    }
+
+    #[test]
+    fn test_block_state() {
+        let is_letter = |c: char| c.is_ascii_alphabetic();
+        let mut state = BlockState::default();
+        assert_eq!(state.cut("123", 2, is_letter), 0);
+        assert_eq!(state.cut("123  a", 2, is_letter), 5);
+        assert_eq!(state.cut("abc, de!", 2, is_letter), 6);
+        assert_eq!(state.cut("de!fg", 2, is_letter), 4);
+
+        let mut state = BlockState::default();
+        assert_eq!(state.cut("abcd", 1, is_letter), 3);
+        assert_eq!(state.cut("d", 1, is_letter), 0);
+        assert_eq!(state.cut("d...e", 1, is_letter), 4);
+    }
+}
@@ -0,0 +1,174 @@
+use base64::{Engine as _, engine::general_purpose};
+
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+
+/// A cipher over raw bytes that keeps the length of its input, so it can work
+/// in place on a buffer or a file.
+///
+/// `TextBytes` runs any `Cipher` over UTF-8 bytes, and `Base64Text` goes the
+/// other way, making a `ByteCipher` usable wherever a `Cipher` is expected.
+pub trait ByteCipher {
+    fn encrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError>;
+    fn decrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError>;
+    fn name(&self) -> &str;
+
+    fn encrypt_bytes(&self, plaintext: &[u8]) -> Result<Vec<u8>, PolygraphiaError> {
+        let mut data = plaintext.to_vec();
+        self.encrypt_in_place(&mut data)?;
+        Ok(data)
+    }
+
+    fn decrypt_bytes(&self, ciphertext: &[u8]) -> Result<Vec<u8>, PolygraphiaError> {
+        let mut data = ciphertext.to_vec();
+        self.decrypt_in_place(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl<T: ByteCipher + ?Sized> ByteCipher for &T {
+    fn encrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        (**self).encrypt_in_place(data)
+    }
+
+    fn decrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        (**self).decrypt_in_place(data)
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+}
+
+impl<T: ByteCipher + ?Sized> ByteCipher for Box<T> {
+    fn encrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        (**self).encrypt_in_place(data)
+    }
+
+    fn decrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        (**self).decrypt_in_place(data)
+    }
+
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+}
+
+/// Runs a text `Cipher` over bytes holding UTF-8 text.
+///
+/// The bytes must be valid UTF-8, and the cipher must keep the byte length of
+/// the text: ciphers that pad (Hill, Playfair) or drop characters
+/// (`TextMode::AlphaOnly`) fail with an error instead.
+#[derive(Debug, Clone)]
+pub struct TextBytes<C>(pub C);
+
+impl<C: Cipher> ByteCipher for TextBytes<C> {
+    fn encrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        self.0.encrypt_bytes_in_place(data)
+    }
+
+    fn decrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        self.0.decrypt_bytes_in_place(data)
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+/// Exposes a `ByteCipher` as a text `Cipher`: the UTF-8 bytes of the plaintext
+/// are encrypted and the ciphertext is written in standard base64.
+#[derive(Debug, Clone)]
+pub struct Base64Text<B>(pub B);
+
+impl<B: ByteCipher> Cipher for Base64Text<B> {
+    fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError> {
+        let data = self.0.encrypt_bytes(plaintext.as_bytes())?;
+        Ok(general_purpose::STANDARD.encode(data))
+    }
+
+    fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError> {
+        let mut data = general_purpose::STANDARD
+            .decode(ciphertext.trim())
+            .map_err(|e| PolygraphiaError::DecryptionError(format!("Invalid base64: {e}")))?;
+        self.0.decrypt_in_place(&mut data)?;
+        String::from_utf8(data).map_err(|_| {
+            PolygraphiaError::DecryptionError(
+                "Decrypted data is not valid UTF-8; wrong key?".to_string(),
+            )
+        })
+    }
+
+    fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classic::{Caesar, Playfair};
+
+    /// Repeating-key XOR, standing in for a real byte cipher.
+    struct Xor(Vec<u8>);
+
+    impl ByteCipher for Xor {
+        fn encrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+            for (byte, key) in data.iter_mut().zip(self.0.iter().cycle()) {
+                *byte ^= key;
+            }
+            Ok(())
+        }
+
+        fn decrypt_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+            self.encrypt_in_place(data)
+        }
+
+        fn name(&self) -> &str {
+            "xor"
+        }
+    }
+
+    #[test]
+    fn test_text_bytes() {
+        let cipher = TextBytes(Caesar::new(3).unwrap());
+        let mut data = b"Hello, World!".to_vec();
+        cipher.encrypt_in_place(&mut data).unwrap();
+        assert_eq!(data, b"Khoor, Zruog!");
+        assert_eq!(cipher.decrypt_bytes(&data).unwrap(), b"Hello, World!");
+        assert_eq!(ByteCipher::name(&cipher), "caesar");
+
+        assert!(cipher.encrypt_in_place(&mut [0xff, 0xfe]).is_err());
+
+        // Playfair pads "cat" to four letters
+        let playfair = TextBytes(Playfair::new("secret").unwrap());
+        let mut data = b"cat".to_vec();
+        assert!(playfair.encrypt_in_place(&mut data).is_err());
+        assert_eq!(data, b"cat");
+    }
+
+    #[test]
+    fn test_base64_text() {
+        let cipher = Base64Text(Xor(b"key".to_vec()));
+        let ciphertext = cipher.encrypt("Attack at dawn").unwrap();
+        assert_eq!(ciphertext, "KhENCgYSSwQNSwEYHAs=");
+        assert_eq!(cipher.decrypt(&ciphertext).unwrap(), "Attack at dawn");
+        assert!(cipher.decrypt("not base64!").is_err());
+
+        let boxed: Box<dyn Cipher> = Box::new(cipher);
+        assert_eq!(boxed.name(), "xor");
+    }
+
+    #[test]
+    fn test_byte_cipher_objects() {
+        let ciphers: Vec<Box<dyn ByteCipher>> = vec![
+            Box::new(Xor(vec![0x20])),
+            Box::new(TextBytes(Caesar::new(1).unwrap())),
+        ];
+        for cipher in &ciphers {
+            let encrypted = cipher.encrypt_bytes(b"abc").unwrap();
+            assert_ne!(encrypted, b"abc");
+            assert_eq!((&cipher).decrypt_bytes(&encrypted).unwrap(), b"abc");
+        }
+    }
+}
@@ -4,4 +4,51 @@ pub trait Cipher {
     fn encrypt(&self, plaintext: &str) -> Result<String, PolygraphiaError>;
     fn decrypt(&self, ciphertext: &str) -> Result<String, PolygraphiaError>;
     fn name(&self) -> &str;
+
+    /// Appends the encryption of `plaintext` to `out`, so one buffer can be
+    /// reused across many messages.
+    fn encrypt_into(&self, plaintext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        out.push_str(&self.encrypt(plaintext)?);
+        Ok(())
+    }
+
+    fn decrypt_into(&self, ciphertext: &str, out: &mut String) -> Result<(), PolygraphiaError> {
+        out.push_str(&self.decrypt(ciphertext)?);
+        Ok(())
+    }
+
+    /// Encrypts UTF-8 text held in `data` in place. Fails, leaving `data`
+    /// untouched, when the ciphertext would not have the same byte length.
+    ///
+    /// The default encrypts into a new string and copies it back, which only
+    /// makes sense for ciphers that may change the length. Ciphers that map
+    /// every character to one of the same length, like Caesar, Affine,
+    /// Substitution, Vigenère and Enigma, work on `data` without allocating.
+    fn encrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        process_bytes_in_place(data, self.name(), |text, out| self.encrypt_into(text, out))
+    }
+
+    fn decrypt_bytes_in_place(&self, data: &mut [u8]) -> Result<(), PolygraphiaError> {
+        process_bytes_in_place(data, self.name(), |text, out| self.decrypt_into(text, out))
+    }
+}
+
+fn process_bytes_in_place(
+    data: &mut [u8],
+    name: &str,
+    transform: impl FnOnce(&str, &mut String) -> Result<(), PolygraphiaError>,
+) -> Result<(), PolygraphiaError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|e| PolygraphiaError::InvalidInput(format!("Data is not valid UTF-8: {e}")))?;
+    let mut output = String::with_capacity(text.len());
+    transform(text, &mut output)?;
+    if output.len() != data.len() {
+        return Err(PolygraphiaError::InvalidInput(format!(
+            "{name} changed the length of the text from {} to {} bytes",
+            data.len(),
+            output.len()
+        )));
+    }
+    data.copy_from_slice(output.as_bytes());
+    Ok(())
 }
@@ -1,2 +1,6 @@
+pub mod byte_cipher;
 pub mod cipher;
+pub mod stream_cipher;
+pub use byte_cipher::{Base64Text, ByteCipher, TextBytes};
 pub use cipher::Cipher;
+pub use stream_cipher::StreamCipher;
@@ -0,0 +1,47 @@
+use crate::error::PolygraphiaError;
+use crate::traits::Cipher;
+
+/// A cipher that can work through a message piece by piece, as `EncryptWriter`
+/// and `DecryptReader` do, with the same result as a single `encrypt` or
+/// `decrypt` call.
+///
+/// Whatever one piece hands on to the next, such as rotor positions or the
+/// letters of an autokey, lives in `State`, which starts from `Default` for
+/// every message. Ciphers that need the whole message before writing anything,
+/// like the transpositions, Bifid, Trifid and ADFGVX, do not implement it.
+pub trait StreamCipher: Cipher {
+    type State: Default;
+
+    /// Encrypts the start of `text` that does not depend on what may follow it,
+    /// appending to `out` and returning its length in bytes. The rest is passed
+    /// again, followed by the next piece, or to `encrypt_final`; `state` can
+    /// note how much of it has been looked at so it is not scanned twice.
+    fn encrypt_partial(
+        &self,
+        text: &str,
+        state: &mut Self::State,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError>;
+
+    fn decrypt_partial(
+        &self,
+        text: &str,
+        state: &mut Self::State,
+        out: &mut String,
+    ) -> Result<usize, PolygraphiaError>;
+
+    /// Encrypts the end of a message started with `encrypt_partial`, padding it.
+    fn encrypt_final(
+        &self,
+        text: &str,
+        state: &mut Self::State,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError>;
+
+    fn decrypt_final(
+        &self,
+        text: &str,
+        state: &mut Self::State,
+        out: &mut String,
+    ) -> Result<(), PolygraphiaError>;
+}
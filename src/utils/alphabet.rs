@@ -0,0 +1,391 @@
+use crate::error::PolygraphiaError;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMapping {
+    /// Upper and lower case share a symbol; processed text keeps the input case.
+    #[default]
+    Insensitive,
+    /// Every symbol is matched exactly as given.
+    Sensitive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArabicOptions {
+    /// Folds hamza carriers and alef variants (أ إ آ ٱ ؤ ئ ى) into their base letters.
+    pub fold_hamza: bool,
+    /// Reads taa marbuta (ة) as haa (ه).
+    pub taa_marbuta_as_haa: bool,
+}
+
+impl Default for ArabicOptions {
+    fn default() -> Self {
+        ArabicOptions {
+            fold_hamza: true,
+            taa_marbuta_as_haa: false,
+        }
+    }
+}
+
+/// The ordered symbols a cipher works over, with their case handling and aliases.
+///
+/// Symbol indices are stored as bytes, so an alphabet holds at most 254 symbols;
+/// `new` and `with_case` reject longer ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    symbols: Vec<char>,
+    case: CaseMapping,
+    aliases: Vec<(char, char)>,
+    ascii_lookup: [u8; 128],
+}
+
+const NOT_FOUND: u8 = u8::MAX;
+
+impl Alphabet {
+    pub fn new(symbols: &str) -> Result<Self, PolygraphiaError> {
+        Self::with_case(symbols, CaseMapping::default())
+    }
+
+    pub fn with_case(symbols: &str, case: CaseMapping) -> Result<Self, PolygraphiaError> {
+        let symbols: Vec<char> = symbols
+            .chars()
+            .map(|c| match case {
+                CaseMapping::Insensitive => to_lower(c),
+                CaseMapping::Sensitive => c,
+            })
+            .collect();
+        if symbols.len() < 2 {
+            return Err(PolygraphiaError::InvalidKey(
+                "Alphabet must contain at least 2 symbols".to_string(),
+            ));
+        }
+        if symbols.len() >= NOT_FOUND as usize {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Alphabet has {} symbols, at most {} are supported",
+                symbols.len(),
+                NOT_FOUND - 1
+            )));
+        }
+        for (i, c) in symbols.iter().enumerate() {
+            if symbols[..i].contains(c) {
+                return Err(PolygraphiaError::InvalidKey(format!(
+                    "Alphabet contains '{c}' more than once"
+                )));
+            }
+        }
+        let mut alphabet = Alphabet {
+            symbols,
+            case,
+            aliases: Vec::new(),
+            ascii_lookup: [NOT_FOUND; 128],
+        };
+        alphabet.rebuild_lookup();
+        Ok(alphabet)
+    }
+
+    pub fn english() -> Self {
+        Self::new("abcdefghijklmnopqrstuvwxyz").unwrap()
+    }
+
+    /// The 25-letter alphabet of the classic Playfair square, with 'j' read as 'i'.
+    pub fn english_merged_ij() -> Self {
+        Self::english().merge('j', 'i').unwrap()
+    }
+
+    /// The 28 letters of the Arabic alphabet in hijā'ī order.
+    pub fn arabic() -> Self {
+        Self::arabic_with(ArabicOptions::default())
+    }
+
+    pub fn arabic_with(options: ArabicOptions) -> Self {
+        let mut alphabet = Self::new("ابتثجحخدذرزسشصضطظعغفقكلمنهوي").unwrap();
+        if options.fold_hamza {
+            for (from, into) in [
+                ('أ', 'ا'),
+                ('إ', 'ا'),
+                ('آ', 'ا'),
+                ('ٱ', 'ا'),
+                ('ؤ', 'و'),
+                ('ئ', 'ي'),
+                ('ى', 'ي'),
+            ] {
+                alphabet = alphabet.merge(from, into).unwrap();
+            }
+        }
+        if options.taa_marbuta_as_haa {
+            alphabet = alphabet.merge('ة', 'ه').unwrap();
+        }
+        alphabet
+    }
+
+    /// Removes `from` from the symbols (if present) and reads it as `into` from now on.
+    pub fn merge(mut self, from: char, into: char) -> Result<Self, PolygraphiaError> {
+        let from = self.fold(from);
+        let into = self
+            .index_of(into)
+            .map(|i| self.symbols[i])
+            .ok_or_else(|| {
+                PolygraphiaError::InvalidKey(format!(
+                    "Merge target '{into}' is not in the alphabet"
+                ))
+            })?;
+        if from == into {
+            return Ok(self);
+        }
+        self.symbols.retain(|&c| c != from);
+        if self.symbols.len() < 2 {
+            return Err(PolygraphiaError::InvalidKey(
+                "Alphabet must contain at least 2 symbols".to_string(),
+            ));
+        }
+        for alias in &mut self.aliases {
+            if alias.1 == from {
+                alias.1 = into;
+            }
+        }
+        self.aliases.retain(|&(c, _)| c != from);
+        self.aliases.push((from, into));
+        self.rebuild_lookup();
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn case_mapping(&self) -> CaseMapping {
+        self.case
+    }
+
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    pub fn symbol(&self, index: usize) -> char {
+        self.symbols[index]
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.index_of(c).is_some()
+    }
+
+    pub fn index_of(&self, c: char) -> Option<usize> {
+        if c.is_ascii() {
+            let idx = self.ascii_lookup[c as usize];
+            return (idx != NOT_FOUND).then_some(idx as usize);
+        }
+        let c = self.fold(c);
+        let c = self
+            .aliases
+            .iter()
+            .find(|&&(alias, _)| alias == c)
+            .map_or(c, |&(_, target)| target);
+        self.symbols.iter().position(|&s| s == c)
+    }
+
+    /// Returns the symbol at `index`, upper-cased when `reference` is upper case.
+    pub fn symbol_like(&self, index: usize, reference: char) -> char {
+        let symbol = self.symbols[index];
+        match self.case {
+            CaseMapping::Insensitive if reference.is_uppercase() => to_upper(symbol),
+            _ => symbol,
+        }
+    }
+
+    /// Maps `c` onto its canonical symbol, keeping its case.
+    pub fn normalize(&self, c: char) -> Option<char> {
+        self.index_of(c).map(|i| self.symbol_like(i, c))
+    }
+
+    /// Orders the alphabet starting with the unique symbols of `keyword`.
+    pub fn keyword_order(&self, keyword: &str) -> Vec<usize> {
+        let mut seen = vec![false; self.len()];
+        let mut order = Vec::with_capacity(self.len());
+        for idx in keyword
+            .chars()
+            .filter_map(|c| self.index_of(c))
+            .chain(0..self.len())
+        {
+            if !seen[idx] {
+                seen[idx] = true;
+                order.push(idx);
+            }
+        }
+        order
+    }
+
+    fn fold(&self, c: char) -> char {
+        match self.case {
+            CaseMapping::Insensitive => to_lower(c),
+            CaseMapping::Sensitive => c,
+        }
+    }
+
+    fn set_ascii(&mut self, c: char, idx: usize) {
+        if c.is_ascii() {
+            self.ascii_lookup[c as usize] = idx as u8;
+        }
+        if self.case == CaseMapping::Insensitive {
+            let upper = to_upper(c);
+            if upper.is_ascii() {
+                self.ascii_lookup[upper as usize] = idx as u8;
+            }
+        }
+    }
+
+    fn rebuild_lookup(&mut self) {
+        self.ascii_lookup = [NOT_FOUND; 128];
+        for idx in 0..self.symbols.len() {
+            self.set_ascii(self.symbols[idx], idx);
+        }
+        for i in 0..self.aliases.len() {
+            let (alias, target) = self.aliases[i];
+            if let Some(idx) = self.symbols.iter().position(|&s| s == target) {
+                self.set_ascii(alias, idx);
+            }
+        }
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+fn to_lower(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+fn to_upper(c: char) -> char {
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(u), None) => u,
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alphabet_english() {
+        let alphabet = Alphabet::english();
+        assert_eq!(alphabet.len(), 26);
+        assert_eq!(alphabet.index_of('a'), Some(0));
+        assert_eq!(alphabet.index_of('Z'), Some(25));
+        assert_eq!(alphabet.index_of('1'), None);
+        assert_eq!(alphabet.symbol(7), 'h');
+        assert_eq!(Alphabet::default(), alphabet);
+    }
+
+    #[test]
+    fn test_alphabet_invalid() {
+        assert!(Alphabet::new("").is_err());
+        assert!(Alphabet::new("a").is_err());
+        assert!(Alphabet::new("abca").is_err());
+        // Case-insensitive alphabets fold duplicates
+        assert!(Alphabet::new("abA").is_err());
+        assert!(Alphabet::with_case("abA", CaseMapping::Sensitive).is_ok());
+
+        let symbols = |count| ('\u{4e00}'..).take(count).collect::<String>();
+        assert_eq!(Alphabet::new(&symbols(254)).unwrap().len(), 254);
+        assert!(Alphabet::new(&symbols(255)).is_err());
+    }
+
+    #[test]
+    fn test_alphabet_custom_symbols() {
+        let alphabet = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456789 ").unwrap();
+        assert_eq!(alphabet.len(), 37);
+        assert_eq!(alphabet.index_of('0'), Some(26));
+        assert_eq!(alphabet.index_of(' '), Some(36));
+        assert_eq!(alphabet.index_of('Q'), Some(16));
+        assert!(!alphabet.contains('!'));
+    }
+
+    #[test]
+    fn test_alphabet_case_sensitive() {
+        let alphabet = Alphabet::with_case("abcABC", CaseMapping::Sensitive).unwrap();
+        assert_eq!(alphabet.index_of('a'), Some(0));
+        assert_eq!(alphabet.index_of('A'), Some(3));
+        assert_eq!(alphabet.symbol_like(0, 'Z'), 'a');
+    }
+
+    #[test]
+    fn test_alphabet_symbol_like() {
+        let alphabet = Alphabet::english();
+        assert_eq!(alphabet.symbol_like(0, 'Q'), 'A');
+        assert_eq!(alphabet.symbol_like(0, 'q'), 'a');
+        assert_eq!(alphabet.symbol_like(0, '!'), 'a');
+    }
+
+    #[test]
+    fn test_alphabet_merge() {
+        let alphabet = Alphabet::english_merged_ij();
+        assert_eq!(alphabet.len(), 25);
+        assert_eq!(alphabet.index_of('j'), alphabet.index_of('i'));
+        assert_eq!(alphabet.normalize('J'), Some('I'));
+        assert_eq!(alphabet.normalize('k'), Some('k'));
+        assert!(!alphabet.symbols().contains(&'j'));
+
+        // Merging into a missing symbol fails
+        assert!(Alphabet::english().merge('j', '1').is_err());
+    }
+
+    #[test]
+    fn test_alphabet_non_ascii() {
+        let alphabet = Alphabet::new("αβγδ").unwrap();
+        assert_eq!(alphabet.index_of('Γ'), Some(2));
+        assert_eq!(alphabet.symbol_like(1, 'Δ'), 'Β');
+    }
+
+    #[test]
+    fn test_alphabet_arabic() {
+        let alphabet = Alphabet::arabic();
+        assert_eq!(alphabet.len(), 28);
+        assert_eq!(alphabet.index_of('ا'), Some(0));
+        assert_eq!(alphabet.index_of('ي'), Some(27));
+
+        // Hamza and alef variants fold into their base letters
+        for c in ['أ', 'إ', 'آ', 'ٱ'] {
+            assert_eq!(alphabet.normalize(c), Some('ا'));
+        }
+        assert_eq!(alphabet.normalize('ؤ'), Some('و'));
+        assert_eq!(alphabet.normalize('ئ'), Some('ي'));
+        assert_eq!(alphabet.normalize('ى'), Some('ي'));
+
+        // Taa marbuta and diacritics are left alone by default
+        assert!(!alphabet.contains('ة'));
+        assert!(!alphabet.contains('\u{064E}'));
+    }
+
+    #[test]
+    fn test_alphabet_arabic_options() {
+        let alphabet = Alphabet::arabic_with(ArabicOptions {
+            fold_hamza: false,
+            taa_marbuta_as_haa: true,
+        });
+        assert_eq!(alphabet.len(), 28);
+        assert_eq!(alphabet.normalize('ة'), Some('ه'));
+        assert!(!alphabet.contains('أ'));
+    }
+
+    #[test]
+    fn test_alphabet_keyword_order() {
+        let alphabet = Alphabet::english_merged_ij();
+        let order: String = alphabet
+            .keyword_order("Jumping Jacks")
+            .into_iter()
+            .map(|i| alphabet.symbol(i))
+            .collect();
+        assert_eq!(order, "iumpngacksbdefhloqrtvwxyz");
+    }
+}
@@ -0,0 +1,358 @@
+use crate::error::PolygraphiaError;
+use crate::utils::Alphabet;
+use crate::utils::mode::TextMode;
+use crate::utils::padding::{self, Padding, Unpadding};
+use crate::utils::text::{self, Layout};
+
+/// What digraphic ciphers do with two identical letters in a row.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DoubledLetters {
+    /// Puts the filler letter between them, as Playfair must.
+    #[default]
+    Separate,
+    /// Leaves them alone.
+    Keep,
+}
+
+/// Stream state of the digraphic ciphers.
+#[derive(Debug, Default, Clone)]
+pub struct PairState {
+    layout: Layout,
+    /// Bytes of the pending text already scanned, and the best cut among them.
+    scanned: usize,
+    cut: usize,
+    /// Last plaintext letter scanned, and whether an odd number of letters
+    /// come before it once separated.
+    last: Option<char>,
+    odd: bool,
+    /// Offset of the first letter of an unfinished ciphertext pair, and the
+    /// letter.
+    first: Option<(usize, char)>,
+    /// Whether the last whole ciphertext pair decrypts to a filler at the end.
+    ends_in_filler: Option<bool>,
+}
+
+/// How plaintext is split into letter pairs, shared by the digraphic ciphers.
+#[derive(Debug, Clone)]
+pub(crate) struct PairRules {
+    pub(crate) mode: TextMode,
+    pub(crate) filler: char,
+    pub(crate) padding: Padding,
+    pub(crate) unpadding: Unpadding,
+    pub(crate) doubled: DoubledLetters,
+}
+
+impl PairRules {
+    pub(crate) fn new(alphabet: &Alphabet, mode: TextMode, doubled: DoubledLetters) -> Self {
+        PairRules {
+            mode,
+            filler: padding::default_filler(alphabet),
+            padding: Padding::default(),
+            unpadding: Unpadding::default(),
+            doubled,
+        }
+    }
+
+    pub(crate) fn set_filler(
+        &mut self,
+        filler: char,
+        alphabet: &Alphabet,
+    ) -> Result<(), PolygraphiaError> {
+        self.filler = padding::validate_filler(filler, alphabet)?;
+        Ok(())
+    }
+
+    /// Plaintext letters with separators and padding added, ready to pair up.
+    pub(crate) fn prepare(
+        &self,
+        text: &str,
+        alphabet: &Alphabet,
+    ) -> Result<Vec<char>, PolygraphiaError> {
+        let mut prepared = self.separate(text, alphabet)?;
+        padding::pad(&mut prepared, 2, self.filler, self.padding, alphabet)?;
+        Ok(prepared)
+    }
+
+    /// Ciphertext letters, with a filler added if there is an odd number.
+    pub(crate) fn split_ciphertext(&self, text: &str, alphabet: &Alphabet) -> Vec<char> {
+        let mut letters = Self::filter_text(text, alphabet);
+        if letters.len() % 2 == 1 {
+            letters.push(self.filler);
+        }
+        letters
+    }
+
+    pub(crate) fn unpad(
+        &self,
+        letters: Vec<char>,
+        alphabet: &Alphabet,
+    ) -> Result<Vec<char>, PolygraphiaError> {
+        let mut letters = letters;
+        padding::unpad(&mut letters, 2, self.filler, self.unpadding, alphabet)?;
+        Ok(self.unseparate(letters, alphabet))
+    }
+
+    /// Runs every pair of `text` through `pair`, which maps two letters to two
+    /// letters, and appends the result to `out`, laid out according to `mode`.
+    pub(crate) fn process_into<F>(
+        &self,
+        text: &str,
+        alphabet: &Alphabet,
+        encrypt: bool,
+        out: &mut String,
+        mut pair: F,
+    ) -> Result<(), PolygraphiaError>
+    where
+        F: FnMut(char, char) -> Result<(char, char), PolygraphiaError>,
+    {
+        let letters = self.process_letters(text, alphabet, encrypt, true, &mut pair)?;
+        match self.mode {
+            TextMode::PreserveAll => text::restore_layout_into(text, &letters, alphabet, out),
+            TextMode::AlphaOnly => out.extend(letters),
+        }
+        Ok(())
+    }
+
+    /// Like `process_into` for the start of `text` that what follows cannot
+    /// change, without padding; returns its length in bytes.
+    pub(crate) fn process_partial<F>(
+        &self,
+        text: &str,
+        alphabet: &Alphabet,
+        encrypt: bool,
+        state: &mut PairState,
+        out: &mut String,
+        mut pair: F,
+    ) -> Result<usize, PolygraphiaError>
+    where
+        F: FnMut(char, char) -> Result<(char, char), PolygraphiaError>,
+    {
+        let cut = self.stream_boundary(text, alphabet, encrypt, state, &mut pair)?;
+        let piece = &text[..cut];
+        let letters = self.process_letters(piece, alphabet, encrypt, false, &mut pair)?;
+        match self.mode {
+            TextMode::PreserveAll => state.layout.lay_out(piece, &letters, alphabet, out),
+            TextMode::AlphaOnly => out.extend(letters),
+        }
+        Ok(cut)
+    }
+
+    /// Like `process_into` for the rest of a text begun with `process_partial`.
+    pub(crate) fn process_final<F>(
+        &self,
+        text: &str,
+        alphabet: &Alphabet,
+        encrypt: bool,
+        state: &mut PairState,
+        out: &mut String,
+        mut pair: F,
+    ) -> Result<(), PolygraphiaError>
+    where
+        F: FnMut(char, char) -> Result<(char, char), PolygraphiaError>,
+    {
+        let letters = self.process_letters(text, alphabet, encrypt, true, &mut pair)?;
+        match self.mode {
+            TextMode::PreserveAll => state.layout.finish(text, &letters, alphabet, out),
+            TextMode::AlphaOnly => out.extend(letters),
+        }
+        Ok(())
+    }
+
+    /// The processed letters of `text`, padded or unpadded when `finish` is set.
+    fn process_letters<F>(
+        &self,
+        text: &str,
+        alphabet: &Alphabet,
+        encrypt: bool,
+        finish: bool,
+        pair: &mut F,
+    ) -> Result<Vec<char>, PolygraphiaError>
+    where
+        F: FnMut(char, char) -> Result<(char, char), PolygraphiaError>,
+    {
+        let mut letters = match (encrypt, finish) {
+            (true, true) => self.prepare(text, alphabet)?,
+            (true, false) => self.separate(text, alphabet)?,
+            (false, _) => self.split_ciphertext(text, alphabet),
+        };
+        for chunk in letters.chunks_exact_mut(2) {
+            let (first, second) = pair(chunk[0], chunk[1])?;
+            for (slot, c) in chunk.iter_mut().zip([first, second]) {
+                *slot = match self.mode {
+                    TextMode::PreserveAll => {
+                        let idx = alphabet.index_of(c).unwrap_or(0);
+                        alphabet.symbol_like(idx, *slot)
+                    }
+                    TextMode::AlphaOnly => c,
+                };
+            }
+        }
+        Ok(match (encrypt, finish) {
+            (true, _) => letters,
+            (false, true) => self.unpad(letters, alphabet)?,
+            (false, false) => self.unseparate(letters, alphabet),
+        })
+    }
+
+    /// Byte offset in `text` before which pairs can be processed whatever
+    /// follows: the letters before it pair up evenly, at least one letter (one
+    /// pair when decrypting) is left for the padding, and no separator could
+    /// belong on either side of it. As with `BlockState::cut`, `text` must be
+    /// what the previous cut left with the next piece appended.
+    fn stream_boundary<F>(
+        &self,
+        text: &str,
+        alphabet: &Alphabet,
+        encrypt: bool,
+        state: &mut PairState,
+        pair: &mut F,
+    ) -> Result<usize, PolygraphiaError>
+    where
+        F: FnMut(char, char) -> Result<(char, char), PolygraphiaError>,
+    {
+        let separate = self.doubled == DoubledLetters::Separate;
+        let removes_separators = separate && self.unpadding != Unpadding::Keep;
+        let is_filler = |c: char| same_letter(alphabet, c, self.filler);
+        let start = state.scanned;
+        for (i, c) in text[start..].char_indices() {
+            let Some(next) = alphabet.normalize(c) else {
+                continue;
+            };
+            let i = start + i;
+            if encrypt {
+                if let Some(last) = state.last {
+                    state.odd = !state.odd;
+                    if separate && same_letter(alphabet, last, next) {
+                        state.odd = !state.odd;
+                    } else if !state.odd && !(separate && (is_filler(last) || is_filler(next))) {
+                        state.cut = i;
+                    }
+                }
+                state.last = Some(next);
+            } else if let Some((j, first)) = state.first.take() {
+                let (starts, ends) = if removes_separators {
+                    let (a, b) = pair(first, next)?;
+                    (is_filler(a), is_filler(b))
+                } else {
+                    (false, false)
+                };
+                if state
+                    .ends_in_filler
+                    .is_some_and(|before| !before && !starts)
+                {
+                    state.cut = j;
+                }
+                state.ends_in_filler = Some(ends);
+            } else {
+                state.first = Some((i, next));
+            }
+        }
+        let cut = state.cut;
+        state.scanned = text.len() - cut;
+        if let Some((j, _)) = &mut state.first {
+            *j -= cut;
+        }
+        state.cut = 0;
+        Ok(cut)
+    }
+
+    /// Letters with separators between doubled letters, unpadded.
+    fn separate(&self, text: &str, alphabet: &Alphabet) -> Result<Vec<char>, PolygraphiaError> {
+        let filtered = Self::filter_text(text, alphabet);
+        Ok(match self.doubled {
+            DoubledLetters::Separate => {
+                let prepared = self.insert_separators(&filtered, alphabet);
+                if self.padding == Padding::LengthMarker
+                    && self.remove_separators(&prepared, alphabet) != filtered
+                {
+                    return Err(PolygraphiaError::EncryptionError(format!(
+                        "Plaintext contains '{}' between two identical letters, which cannot be told apart from a separator; choose another filler",
+                        self.filler
+                    )));
+                }
+                prepared
+            }
+            DoubledLetters::Keep => filtered,
+        })
+    }
+
+    /// Drops the separators from decrypted letters, if unpadding asks for it.
+    fn unseparate(&self, letters: Vec<char>, alphabet: &Alphabet) -> Vec<char> {
+        match (self.unpadding, self.doubled) {
+            (Unpadding::Keep, _) | (_, DoubledLetters::Keep) => letters,
+            (Unpadding::Heuristic | Unpadding::Exact, DoubledLetters::Separate) => {
+                self.remove_separators(&letters, alphabet)
+            }
+        }
+    }
+
+    fn filter_text(text: &str, alphabet: &Alphabet) -> Vec<char> {
+        text.chars().filter_map(|c| alphabet.normalize(c)).collect()
+    }
+
+    fn insert_separators(&self, letters: &[char], alphabet: &Alphabet) -> Vec<char> {
+        let filler_idx = alphabet.index_of(self.filler).unwrap_or(0);
+        let mut prepared = Vec::with_capacity(letters.len() + letters.len() / 2);
+        for (i, &c) in letters.iter().enumerate() {
+            prepared.push(c);
+            if letters
+                .get(i + 1)
+                .is_some_and(|&next| same_letter(alphabet, next, c))
+            {
+                prepared.push(alphabet.symbol_like(filler_idx, c));
+            }
+        }
+        prepared
+    }
+
+    fn remove_separators(&self, letters: &[char], alphabet: &Alphabet) -> Vec<char> {
+        let mut result = Vec::with_capacity(letters.len());
+        let mut skipped = false;
+        for (i, &c) in letters.iter().enumerate() {
+            let is_separator = !skipped
+                && i > 0
+                && same_letter(alphabet, c, self.filler)
+                && letters
+                    .get(i + 1)
+                    .is_some_and(|&next| same_letter(alphabet, next, letters[i - 1]));
+            if !is_separator {
+                result.push(c);
+            }
+            skipped = is_separator;
+        }
+        result
+    }
+}
+
+fn same_letter(alphabet: &Alphabet, a: char, b: char) -> bool {
+    alphabet.index_of(a) == alphabet.index_of(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prepared(rules: &PairRules, text: &str) -> String {
+        let alphabet = Alphabet::english_merged_ij();
+        rules
+            .prepare(text, &alphabet)
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_pair_rules_doubled_letters() {
+        let alphabet = Alphabet::english_merged_ij();
+        let mut rules = PairRules::new(&alphabet, TextMode::default(), DoubledLetters::Separate);
+        assert_eq!(prepared(&rules, "Balloon"), "Balxloxonx");
+
+        rules.doubled = DoubledLetters::Keep;
+        assert_eq!(prepared(&rules, "Balloon"), "Balloonx");
+
+        // Without separators there is nothing but padding to remove
+        rules.unpadding = Unpadding::Heuristic;
+        let letters: Vec<char> = "exex".chars().collect();
+        assert_eq!(rules.unpad(letters, &alphabet).unwrap(), ['e', 'x', 'e']);
+    }
+}
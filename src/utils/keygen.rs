@@ -0,0 +1,141 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::error::PolygraphiaError;
+use crate::utils::{Alphabet, Matrix, math};
+
+/// Source of random, always-valid cipher keys.
+///
+/// Use `from_seed` for reproducible keys in tests and fixtures.
+#[derive(Debug, Clone)]
+pub struct KeyGenerator {
+    rng: StdRng,
+}
+
+impl KeyGenerator {
+    pub fn new() -> Self {
+        KeyGenerator {
+            rng: StdRng::from_os_rng(),
+        }
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
+        KeyGenerator {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub(crate) fn rng(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    /// A non-zero shift, so the key never maps the alphabet onto itself.
+    pub fn shift(&mut self, alphabet: &Alphabet) -> u8 {
+        self.rng.random_range(1..alphabet.len()) as u8
+    }
+
+    /// A multiplier coprime with the alphabet size, other than 1 when possible.
+    pub fn multiplier(&mut self, alphabet: &Alphabet) -> u8 {
+        let modulus = alphabet.len() as u64;
+        let units: Vec<u8> = (2..modulus)
+            .filter(|&m| math::are_coprime(m, modulus))
+            .map(|m| m as u8)
+            .collect();
+        if units.is_empty() {
+            1
+        } else {
+            units[self.rng.random_range(0..units.len())]
+        }
+    }
+
+    pub fn word(&mut self, alphabet: &Alphabet, length: usize) -> String {
+        (0..length)
+            .map(|_| alphabet.symbol(self.rng.random_range(0..alphabet.len())))
+            .collect()
+    }
+
+    /// Every symbol of the alphabet exactly once, in random order.
+    pub fn permutation(&mut self, alphabet: &Alphabet) -> String {
+        let mut symbols = alphabet.symbols().to_vec();
+        symbols.shuffle(&mut self.rng);
+        symbols.into_iter().collect()
+    }
+
+    /// A random `size`x`size` matrix that is invertible mod `modulus`.
+    pub fn invertible_matrix(
+        &mut self,
+        size: usize,
+        modulus: u64,
+    ) -> Result<Matrix, PolygraphiaError> {
+        if size == 0 || modulus < 2 {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "Cannot build an invertible {size}x{size} matrix mod {modulus}"
+            )));
+        }
+        loop {
+            let data = (0..size * size)
+                .map(|_| self.rng.random_range(0..modulus))
+                .collect();
+            let matrix = Matrix::new(size, data)?;
+            if math::are_coprime(matrix.determinant(modulus)?, modulus) {
+                return Ok(matrix);
+            }
+        }
+    }
+}
+
+impl Default for KeyGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_generators_agree() {
+        let alphabet = Alphabet::english();
+        let mut a = KeyGenerator::from_seed(42);
+        let mut b = KeyGenerator::from_seed(42);
+        assert_eq!(a.permutation(&alphabet), b.permutation(&alphabet));
+        assert_eq!(a.word(&alphabet, 10), b.word(&alphabet, 10));
+        assert_eq!(
+            a.invertible_matrix(4, 26).unwrap(),
+            b.invertible_matrix(4, 26).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_keys_are_valid() {
+        let mut generator = KeyGenerator::from_seed(7);
+        for alphabet in [Alphabet::english(), Alphabet::arabic()] {
+            let modulus = alphabet.len() as u64;
+            for _ in 0..50 {
+                let shift = generator.shift(&alphabet);
+                assert!(shift >= 1 && (shift as usize) < alphabet.len());
+
+                let multiplier = generator.multiplier(&alphabet) as u64;
+                assert!(multiplier > 1 && math::are_coprime(multiplier, modulus));
+            }
+
+            let mut permutation: Vec<char> = generator.permutation(&alphabet).chars().collect();
+            permutation.sort_unstable();
+            let mut symbols = alphabet.symbols().to_vec();
+            symbols.sort_unstable();
+            assert_eq!(permutation, symbols);
+
+            let matrix = generator.invertible_matrix(6, modulus).unwrap();
+            assert!(matrix.mod_inverse(modulus).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_invertible_matrix_invalid_size() {
+        let mut generator = KeyGenerator::from_seed(1);
+        assert!(generator.invertible_matrix(0, 26).is_err());
+        assert!(generator.invertible_matrix(2, 1).is_err());
+    }
+}
@@ -1,27 +1,44 @@
 use crate::PolygraphiaError;
 
-pub fn gcd(a: u8, b: u8) -> u8 {
+pub fn gcd(a: u64, b: u64) -> u64 {
     if b == 0 { a } else { gcd(b, a % b) }
 }
 
-pub fn are_coprime(a: u8, b: u8) -> bool {
+pub fn lcm(a: u64, b: u64) -> u64 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        a / gcd(a, b) * b
+    }
+}
+
+pub fn are_coprime(a: u64, b: u64) -> bool {
     gcd(a, b) == 1
 }
 
-pub fn mod_inverse(a: u8, m: u8) -> Result<u8, PolygraphiaError> {
-    if !are_coprime(a, m) {
+pub fn mod_add(a: u64, b: u64, m: u64) -> u64 {
+    ((a as u128 + b as u128) % m as u128) as u64
+}
+
+pub fn mod_mul(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+pub fn mod_inverse(a: u64, m: u64) -> Result<u64, PolygraphiaError> {
+    if m == 0 || !are_coprime(a % m, m) {
         return Err(PolygraphiaError::InvalidInput(format!(
             "Modular inverse does not exist for {a} mod {m} (not coprime)"
         )));
     }
-    for i in 1..m {
-        if (a as u16 * i as u16).rem_euclid(m as u16) == 1 {
-            return Ok(i);
-        }
+    // Extended Euclid, tracking the coefficient of `a` only
+    let (mut old_r, mut r) = ((a % m) as i128, m as i128);
+    let (mut old_s, mut s) = (1i128, 0i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
     }
-    Err(PolygraphiaError::InvalidInput(format!(
-        "Failed to compute modular inverse for {a} mod {m}"
-    )))
+    Ok(old_s.rem_euclid(m as i128) as u64)
 }
 
 #[cfg(test)]
@@ -37,6 +54,13 @@ mod tests {
         assert_eq!(gcd(2, 26), 2);
     }
 
+    #[test]
+    fn test_lcm() {
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(7, 7), 7);
+        assert_eq!(lcm(0, 5), 0);
+    }
+
     #[test]
     fn test_mod_inverse() {
         assert_eq!(mod_inverse(3, 26).unwrap(), 9);
@@ -46,9 +70,19 @@ mod tests {
 
         // Verify the inverse property
         for a in [1, 3, 5, 7, 9, 11, 15, 17, 19, 21, 23, 25] {
-            let inv = mod_inverse(a, 26).unwrap() as u16;
-            assert_eq!((a as u16 * inv) % 26, 1);
+            let inv = mod_inverse(a, 26).unwrap();
+            assert_eq!((a * inv) % 26, 1);
+        }
+    }
+
+    #[test]
+    fn test_mod_inverse_large_modulus() {
+        let m = u64::MAX - 58; // largest 64-bit prime
+        for a in [2, 12345, m - 1, 1 << 63] {
+            let inv = mod_inverse(a, m).unwrap();
+            assert_eq!(mod_mul(a, inv, m), 1);
         }
+        assert!(mod_inverse(1000, 1 << 40).is_err());
     }
 
     #[test]
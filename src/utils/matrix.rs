@@ -4,11 +4,11 @@ use crate::utils::math;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Matrix {
     size: usize,
-    data: Vec<i32>,
+    data: Vec<u64>,
 }
 
 impl Matrix {
-    pub fn new(size: usize, data: Vec<i32>) -> Result<Self, PolygraphiaError> {
+    pub fn new(size: usize, data: Vec<u64>) -> Result<Self, PolygraphiaError> {
         if data.len() != size * size {
             return Err(PolygraphiaError::InvalidInput(format!(
                 "Matrix data length {} doesn't match size {size}x{size}",
@@ -18,98 +18,255 @@ impl Matrix {
         Ok(Matrix { size, data })
     }
 
+    pub fn identity(size: usize) -> Self {
+        let mut data = vec![0; size * size];
+        for i in 0..size {
+            data[i * size + i] = 1;
+        }
+        Matrix { size, data }
+    }
+
     pub fn size(&self) -> usize {
         self.size
     }
 
-    pub fn get(&self, row: usize, col: usize) -> i32 {
+    pub fn get(&self, row: usize, col: usize) -> u64 {
         self.data[row * self.size + col]
     }
 
-    pub fn set(&mut self, row: usize, col: usize, value: i32) {
+    pub fn set(&mut self, row: usize, col: usize, value: u64) {
         self.data[row * self.size + col] = value;
     }
 
-    pub fn determinant(&self) -> i32 {
-        match self.size {
-            1 => self.data[0],
-            2 => self.get(0, 0) * self.get(1, 1) - self.get(0, 1) * self.get(1, 0),
-            3 => {
-                let a = self.get(0, 0)
-                    * (self.get(1, 1) * self.get(2, 2) - self.get(1, 2) * self.get(2, 1));
-                let b = self.get(0, 1)
-                    * (self.get(1, 0) * self.get(2, 2) - self.get(1, 2) * self.get(2, 0));
-                let c = self.get(0, 2)
-                    * (self.get(1, 0) * self.get(2, 1) - self.get(1, 1) * self.get(2, 0));
-                a - b + c
-            }
-            _ => {
-                let mut det = 0;
-                for col in 0..self.size {
-                    let minor = self.minor(0, col);
-                    let cofactor = if col % 2 == 0 { 1 } else { -1 };
-                    det += cofactor * self.get(0, col) * minor.determinant();
-                }
-                det
-            }
+    /// Determinant mod `modulus`, computed by row reduction in O(n^3).
+    pub fn determinant(&self, modulus: u64) -> Result<u64, PolygraphiaError> {
+        if modulus == 0 {
+            return Err(PolygraphiaError::InvalidInput(
+                "Modulus must be positive".to_string(),
+            ));
         }
+        let mut rows = self.rows(modulus, false);
+        Ok(triangularize(&mut rows, self.size, modulus))
     }
 
-    fn minor(&self, skip_row: usize, skip_col: usize) -> Matrix {
-        let new_size = self.size - 1;
-        let mut data = Vec::with_capacity(new_size * new_size);
-        for row in 0..self.size {
-            if row == skip_row {
-                continue;
+    pub fn mod_inverse(&self, modulus: u64) -> Result<Matrix, PolygraphiaError> {
+        if modulus < 2 {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "Modulus {modulus} is too small to invert a matrix"
+            )));
+        }
+        let n = self.size;
+        let mut rows = self.rows(modulus, true);
+        let det = triangularize(&mut rows, n, modulus);
+        if !math::are_coprime(det, modulus) {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "Matrix determinant {det} is not coprime with {modulus}"
+            )));
+        }
+        // With a unit determinant every pivot is a unit, so finish Gauss-Jordan
+        for col in (0..n).rev() {
+            let pivot_inv = math::mod_inverse(rows[col][col], modulus)?;
+            for value in rows[col].iter_mut() {
+                *value = math::mod_mul(*value, pivot_inv, modulus);
             }
-            for col in 0..self.size {
-                if col == skip_col {
-                    continue;
+            for row in 0..col {
+                let factor = rows[row][col];
+                if factor != 0 {
+                    subtract_row(&mut rows, row, col, factor, modulus);
                 }
-                data.push(self.get(row, col));
             }
         }
-        Matrix::new(new_size, data).unwrap()
+        let data = rows.into_iter().flat_map(|row| row[n..].to_vec()).collect();
+        Matrix::new(n, data)
     }
 
-    fn adjugate(&self) -> Matrix {
-        let mut adj_data = vec![0; self.size * self.size];
-        for row in 0..self.size {
-            for col in 0..self.size {
-                let minor = self.minor(row, col);
-                let cofactor = if (row + col) % 2 == 0 { 1 } else { -1 };
-                adj_data[col * self.size + row] = cofactor * minor.determinant();
-            }
+    pub fn multiply_vector(&self, vec: &[u64], modulus: u64) -> Vec<u64> {
+        let mut result = vec![0; self.size];
+        self.multiply_vector_into(vec, modulus, &mut result);
+        result
+    }
+
+    /// Writes the product with `vec` into the first `size` entries of `out`.
+    pub fn multiply_vector_into(&self, vec: &[u64], modulus: u64, out: &mut [u64]) {
+        for (row, slot) in out.iter_mut().take(self.size).enumerate() {
+            *slot = vec
+                .iter()
+                .take(self.size)
+                .enumerate()
+                .fold(0, |sum, (col, &x)| {
+                    math::mod_add(sum, math::mod_mul(self.get(row, col), x, modulus), modulus)
+                });
         }
-        Matrix::new(self.size, adj_data).unwrap()
     }
 
-    pub fn mod_inverse(&self, modulus: i32) -> Result<Matrix, PolygraphiaError> {
-        let det = self.determinant();
-        let det_mod = det.rem_euclid(modulus);
-        if math::gcd(det_mod as u8, modulus as u8) != 1 {
-            return Err(PolygraphiaError::InvalidKey(format!(
-                "Matrix determinant {det_mod} is not coprime with {modulus}"
+    pub fn multiply(&self, other: &Matrix, modulus: u64) -> Result<Matrix, PolygraphiaError> {
+        if self.size != other.size {
+            return Err(PolygraphiaError::InvalidInput(format!(
+                "Cannot multiply {0}x{0} and {1}x{1} matrices",
+                self.size, other.size
             )));
         }
-        let det_inv = math::mod_inverse(det_mod as u8, modulus as u8)? as i32;
-        let adj = self.adjugate();
-        let mut inv_data = vec![0; self.size * self.size];
-        for (i, item) in inv_data.iter_mut().enumerate().take(self.size * self.size) {
-            *item = (adj.data[i] * det_inv).rem_euclid(modulus);
+        let n = self.size;
+        let mut data = vec![0; n * n];
+        for row in 0..n {
+            for col in 0..n {
+                data[row * n + col] = (0..n).fold(0, |sum, k| {
+                    math::mod_add(
+                        sum,
+                        math::mod_mul(self.get(row, k), other.get(k, col), modulus),
+                        modulus,
+                    )
+                });
+            }
         }
-        Matrix::new(self.size, inv_data)
+        Matrix::new(n, data)
     }
 
-    pub fn multiply_vector(&self, vec: &[i32]) -> Vec<i32> {
-        let mut result = vec![0; self.size];
-        for (row, item) in result.iter_mut().enumerate().take(self.size) {
-            let mut sum = 0;
-            for (col, _item) in vec.iter().enumerate().take(self.size) {
-                sum += self.get(row, col) * vec[col];
+    fn rows(&self, modulus: u64, augmented: bool) -> Vec<Vec<u64>> {
+        let n = self.size;
+        (0..n)
+            .map(|row| {
+                let mut values: Vec<u64> = self.data[row * n..(row + 1) * n]
+                    .iter()
+                    .map(|&x| x % modulus)
+                    .collect();
+                if augmented {
+                    values.extend((0..n).map(|col| u64::from(col == row) % modulus));
+                }
+                values
+            })
+            .collect()
+    }
+}
+
+/// Brings the leading `n` columns to upper-triangular form using only
+/// unimodular row operations, returning the determinant mod `modulus`.
+///
+/// Pivots need not be invertible: each column is cleared by repeated
+/// Euclidean division between rows, which works for composite moduli.
+fn triangularize(rows: &mut [Vec<u64>], n: usize, modulus: u64) -> u64 {
+    let mut det = 1 % modulus;
+    for col in 0..n {
+        loop {
+            let Some(pivot) = (col..n)
+                .filter(|&row| rows[row][col] != 0)
+                .min_by_key(|&row| rows[row][col])
+            else {
+                return 0;
+            };
+            if pivot != col {
+                rows.swap(pivot, col);
+                det = (modulus - det) % modulus;
+            }
+            let mut cleared = true;
+            for row in col + 1..n {
+                let quotient = rows[row][col] / rows[col][col];
+                subtract_row(rows, row, col, quotient, modulus);
+                cleared &= rows[row][col] == 0;
+            }
+            if cleared {
+                break;
             }
-            *item = sum;
         }
-        result
+        det = math::mod_mul(det, rows[col][col], modulus);
+    }
+    det
+}
+
+/// `rows[target] -= factor * rows[source]` (mod `modulus`).
+fn subtract_row(rows: &mut [Vec<u64>], target: usize, source: usize, factor: u64, modulus: u64) {
+    for k in 0..rows[target].len() {
+        let delta = math::mod_mul(factor, rows[source][k], modulus);
+        rows[target][k] = math::mod_add(rows[target][k], modulus - delta, modulus);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudo_random_matrix(size: usize, modulus: u64, seed: u64) -> Matrix {
+        let mut state = seed;
+        let data = (0..size * size)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 33) % modulus
+            })
+            .collect();
+        Matrix::new(size, data).unwrap()
+    }
+
+    #[test]
+    fn test_determinant_matches_cofactor_expansion() {
+        let matrix = Matrix::new(3, vec![6, 24, 1, 13, 16, 10, 20, 17, 15]).unwrap();
+        // 6(240-170) - 24(195-200) + 1(221-320) = 441
+        assert_eq!(matrix.determinant(26).unwrap(), 441 % 26);
+        assert_eq!(matrix.determinant(1000).unwrap(), 441);
+        assert_eq!(matrix.determinant(1).unwrap(), 0);
+        assert!(matrix.determinant(0).is_err());
+
+        let singular = Matrix::new(2, vec![2, 4, 1, 2]).unwrap();
+        assert_eq!(singular.determinant(26).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_determinant_composite_modulus_without_unit_pivots() {
+        // No entry of the first column is a unit mod 6, yet det = -1
+        let matrix = Matrix::new(2, vec![2, 1, 3, 1]).unwrap();
+        assert_eq!(matrix.determinant(6).unwrap(), 5);
+        let inv = matrix.mod_inverse(6).unwrap();
+        assert_eq!(matrix.multiply(&inv, 6).unwrap(), Matrix::identity(2));
+    }
+
+    #[test]
+    fn test_mod_inverse_large_matrices() {
+        for (size, modulus) in [(8, 26), (12, 29), (16, 95), (20, 254)] {
+            let mut seed = size as u64;
+            let matrix = loop {
+                let candidate = pseudo_random_matrix(size, modulus, seed);
+                if math::are_coprime(candidate.determinant(modulus).unwrap(), modulus) {
+                    break candidate;
+                }
+                seed += 1000;
+            };
+            let inv = matrix.mod_inverse(modulus).unwrap();
+            assert_eq!(
+                matrix.multiply(&inv, modulus).unwrap(),
+                Matrix::identity(size)
+            );
+            assert_eq!(
+                inv.multiply(&matrix, modulus).unwrap(),
+                Matrix::identity(size)
+            );
+        }
+    }
+
+    #[test]
+    fn test_mod_inverse_u64_modulus() {
+        let modulus = u64::MAX - 58;
+        let matrix = Matrix::new(2, vec![modulus - 1, 3, 5, modulus - 7]).unwrap();
+        let inv = matrix.mod_inverse(modulus).unwrap();
+        assert_eq!(matrix.multiply(&inv, modulus).unwrap(), Matrix::identity(2));
+    }
+
+    #[test]
+    fn test_mod_inverse_not_invertible() {
+        let matrix = Matrix::new(2, vec![2, 4, 1, 3]).unwrap();
+        // det = 2
+        assert!(matrix.mod_inverse(26).is_err());
+        assert!(matrix.mod_inverse(27).is_ok());
+        assert!(matrix.mod_inverse(1).is_err());
+    }
+
+    #[test]
+    fn test_multiply_vector() {
+        let matrix = Matrix::new(2, vec![3, 3, 2, 5]).unwrap();
+        assert_eq!(matrix.multiply_vector(&[7, 8], 26), vec![45 % 26, 54 % 26]);
+
+        let mut out = [1; 3];
+        matrix.multiply_vector_into(&[7, 8], 26, &mut out);
+        assert_eq!(out, [45 % 26, 54 % 26, 1]);
     }
 }
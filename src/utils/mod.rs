@@ -1,9 +1,20 @@
+pub mod alphabet;
+pub mod digraph;
 pub mod mode;
 pub mod math;
 pub mod matrix;
 pub mod kdf;
+pub mod keygen;
+pub mod padding;
+pub mod polybius;
+pub(crate) mod text;
 
+pub use alphabet::{Alphabet, ArabicOptions, CaseMapping};
+pub use digraph::{DoubledLetters, PairState};
 pub use mode::TextMode;
 pub use math::*;
 pub use matrix::Matrix;
 pub use kdf::*;
+pub use keygen::KeyGenerator;
+pub use padding::{Padding, Unpadding};
+pub use polybius::PolybiusSquare;
@@ -0,0 +1,214 @@
+use crate::error::PolygraphiaError;
+use crate::utils::Alphabet;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// Pads the last block with the filler letter only.
+    #[default]
+    Filler,
+    /// Always pads, ending with a marker letter whose alphabet index is the pad length.
+    LengthMarker,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unpadding {
+    /// Returns decrypted text with all fillers left in.
+    #[default]
+    Keep,
+    /// Removes letters that look like fillers.
+    Heuristic,
+    /// Removes exactly what `Padding::LengthMarker` recorded.
+    Exact,
+}
+
+pub(crate) fn default_filler(alphabet: &Alphabet) -> char {
+    alphabet
+        .normalize('x')
+        .unwrap_or_else(|| alphabet.symbol(alphabet.len() - 1))
+}
+
+pub(crate) fn validate_filler(filler: char, alphabet: &Alphabet) -> Result<char, PolygraphiaError> {
+    alphabet
+        .index_of(filler)
+        .map(|idx| alphabet.symbol(idx))
+        .ok_or_else(|| {
+            PolygraphiaError::InvalidKey(format!("Filler '{filler}' is not in the alphabet"))
+        })
+}
+
+pub(crate) fn pad(
+    letters: &mut Vec<char>,
+    block_size: usize,
+    filler: char,
+    padding: Padding,
+    alphabet: &Alphabet,
+) -> Result<(), PolygraphiaError> {
+    let remainder = letters.len() % block_size;
+    let last = letters.last().copied().unwrap_or(filler);
+    let filler_idx = alphabet.index_of(filler).unwrap_or(0);
+    let filler = alphabet.symbol_like(filler_idx, last);
+    match padding {
+        Padding::Filler => {
+            if remainder != 0 {
+                letters.extend(std::iter::repeat_n(filler, block_size - remainder));
+            }
+        }
+        Padding::LengthMarker => {
+            if block_size >= alphabet.len() {
+                return Err(PolygraphiaError::EncryptionError(format!(
+                    "Block size {block_size} is too large for a length marker"
+                )));
+            }
+            let pad_len = block_size - remainder;
+            letters.extend(std::iter::repeat_n(filler, pad_len - 1));
+            letters.push(alphabet.symbol_like(pad_len, last));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn unpad(
+    letters: &mut Vec<char>,
+    block_size: usize,
+    filler: char,
+    unpadding: Unpadding,
+    alphabet: &Alphabet,
+) -> Result<(), PolygraphiaError> {
+    let filler_idx = alphabet.index_of(filler);
+    let is_filler = |c: &char| alphabet.index_of(*c) == filler_idx;
+    match unpadding {
+        Unpadding::Keep => {}
+        Unpadding::Heuristic => {
+            let mut removed = 0;
+            while removed < block_size - 1 && letters.last().is_some_and(is_filler) {
+                letters.pop();
+                removed += 1;
+            }
+        }
+        Unpadding::Exact => {
+            let pad_len = letters
+                .last()
+                .and_then(|&c| alphabet.index_of(c))
+                .filter(|&n| n >= 1 && n <= block_size && n <= letters.len())
+                .ok_or_else(|| {
+                    PolygraphiaError::DecryptionError(
+                        "Ciphertext does not end with a valid length marker".to_string(),
+                    )
+                })?;
+            let start = letters.len() - pad_len;
+            if !letters[start..letters.len() - 1].iter().all(is_filler) {
+                return Err(PolygraphiaError::DecryptionError(
+                    "Padding before the length marker does not match the filler".to_string(),
+                ));
+            }
+            letters.truncate(start);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn padded(text: &str, block_size: usize, padding: Padding) -> String {
+        let mut letters: Vec<char> = text.chars().collect();
+        pad(&mut letters, block_size, 'x', padding, &Alphabet::english()).unwrap();
+        letters.into_iter().collect()
+    }
+
+    fn unpadded(
+        text: &str,
+        block_size: usize,
+        unpadding: Unpadding,
+    ) -> Result<String, PolygraphiaError> {
+        let mut letters: Vec<char> = text.chars().collect();
+        unpad(
+            &mut letters,
+            block_size,
+            'x',
+            unpadding,
+            &Alphabet::english(),
+        )?;
+        Ok(letters.into_iter().collect())
+    }
+
+    #[test]
+    fn test_pad_filler() {
+        assert_eq!(padded("cat", 2, Padding::Filler), "catx");
+        assert_eq!(padded("cats", 2, Padding::Filler), "cats");
+        assert_eq!(padded("CAT", 3, Padding::Filler), "CAT");
+        assert_eq!(padded("DOGS", 3, Padding::Filler), "DOGSXX");
+    }
+
+    #[test]
+    fn test_pad_length_marker() {
+        assert_eq!(padded("cat", 2, Padding::LengthMarker), "catb");
+        assert_eq!(padded("cats", 2, Padding::LengthMarker), "catsxc");
+        assert_eq!(padded("dogs", 3, Padding::LengthMarker), "dogsxc");
+        assert_eq!(padded("DOG", 3, Padding::LengthMarker), "DOGXXD");
+    }
+
+    #[test]
+    fn test_unpad_keep() {
+        assert_eq!(unpadded("catx", 2, Unpadding::Keep).unwrap(), "catx");
+    }
+
+    #[test]
+    fn test_unpad_heuristic() {
+        assert_eq!(unpadded("catx", 2, Unpadding::Heuristic).unwrap(), "cat");
+        assert_eq!(unpadded("dogsxx", 3, Unpadding::Heuristic).unwrap(), "dogs");
+
+        // Genuine trailing fillers are indistinguishable from padding
+        assert_eq!(unpadded("boxx", 2, Unpadding::Heuristic).unwrap(), "box");
+    }
+
+    #[test]
+    fn test_unpad_exact() {
+        for (text, block_size) in [("cat", 2), ("cats", 2), ("box", 2), ("boxx", 3), ("A", 4)] {
+            let padded = padded(text, block_size, Padding::LengthMarker);
+            assert_eq!(
+                unpadded(&padded, block_size, Unpadding::Exact).unwrap(),
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_unpad_exact_invalid_marker() {
+        // 'a' would mean zero padding, 'z' exceeds the block size
+        assert!(unpadded("cata", 2, Unpadding::Exact).is_err());
+        assert!(unpadded("catz", 2, Unpadding::Exact).is_err());
+        // Letters before the marker must be fillers
+        assert!(unpadded("cac", 2, Unpadding::Exact).is_err());
+    }
+
+    #[test]
+    fn test_validate_filler() {
+        let alphabet = Alphabet::english_merged_ij();
+        assert_eq!(validate_filler('Q', &alphabet).unwrap(), 'q');
+        assert_eq!(validate_filler('j', &alphabet).unwrap(), 'i');
+        assert!(validate_filler('1', &alphabet).is_err());
+        assert!(validate_filler('é', &alphabet).is_err());
+    }
+
+    #[test]
+    fn test_default_filler() {
+        assert_eq!(default_filler(&Alphabet::english()), 'x');
+        assert_eq!(default_filler(&Alphabet::new("0123456789").unwrap()), '9');
+    }
+
+    #[test]
+    fn test_pad_custom_alphabet() {
+        let alphabet = Alphabet::new("0123456789").unwrap();
+        let mut letters: Vec<char> = "42".chars().collect();
+        pad(&mut letters, 3, '0', Padding::LengthMarker, &alphabet).unwrap();
+        assert_eq!(letters, ['4', '2', '1']);
+
+        unpad(&mut letters, 3, '0', Unpadding::Exact, &alphabet).unwrap();
+        assert_eq!(letters, ['4', '2']);
+
+        // The marker must fit in the alphabet
+        assert!(pad(&mut letters, 10, '0', Padding::LengthMarker, &alphabet).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use crate::error::PolygraphiaError;
+use crate::utils::Alphabet;
+
+/// A keyword-mixed alphabet laid out in a grid, as used by Playfair, Bifid,
+/// ADFGVX and the other square ciphers.
+///
+/// Cells hold alphabet indices. The key lists the symbols row by row.
+#[derive(Debug, Clone)]
+pub struct PolybiusSquare {
+    key: String,
+    matrix: Vec<Vec<u8>>,
+    positions: Vec<(usize, usize)>,
+    alphabet: Alphabet,
+}
+
+impl PolybiusSquare {
+    /// The keyword's symbols without repeats, then the rest of the alphabet,
+    /// in the squarest grid the alphabet size allows (5x5 for 25 symbols).
+    pub fn new(keyword: &str, alphabet: Alphabet) -> Result<Self, PolygraphiaError> {
+        let (rows, cols) = Self::grid_shape(alphabet.len()).ok_or_else(|| {
+            PolygraphiaError::InvalidKey(format!(
+                "An alphabet of {} symbols cannot be arranged in a rectangular grid",
+                alphabet.len()
+            ))
+        })?;
+        Self::with_shape(keyword, alphabet, rows, cols)
+    }
+
+    pub fn with_shape(
+        keyword: &str,
+        alphabet: Alphabet,
+        rows: usize,
+        cols: usize,
+    ) -> Result<Self, PolygraphiaError> {
+        if rows * cols != alphabet.len() {
+            return Err(PolygraphiaError::InvalidKey(format!(
+                "A {rows}x{cols} grid cannot hold an alphabet of {} symbols",
+                alphabet.len()
+            )));
+        }
+        let order = alphabet.keyword_order(keyword);
+        let mut matrix = vec![vec![0u8; cols]; rows];
+        let mut positions = vec![(0, 0); alphabet.len()];
+        for (i, &idx) in order.iter().enumerate() {
+            matrix[i / cols][i % cols] = idx as u8;
+            positions[idx] = (i / cols, i % cols);
+        }
+        Ok(PolybiusSquare {
+            key: order.into_iter().map(|idx| alphabet.symbol(idx)).collect(),
+            matrix,
+            positions,
+            alphabet,
+        })
+    }
+
+    /// Every symbol in grid order, row by row.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn matrix(&self) -> &[Vec<u8>] {
+        &self.matrix
+    }
+
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+
+    pub fn rows(&self) -> usize {
+        self.matrix.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.matrix[0].len()
+    }
+
+    /// Alphabet index of the symbol in a cell.
+    pub fn index_at(&self, row: usize, col: usize) -> usize {
+        self.matrix[row][col] as usize
+    }
+
+    pub fn symbol_at(&self, row: usize, col: usize) -> char {
+        self.alphabet.symbol(self.index_at(row, col))
+    }
+
+    /// Row and column of the symbol with this alphabet index.
+    pub fn position(&self, index: usize) -> (usize, usize) {
+        self.positions[index]
+    }
+
+    /// Row and column of `c`, or `None` if it is not in the alphabet.
+    pub fn coordinates(&self, c: char) -> Option<(usize, usize)> {
+        self.alphabet.index_of(c).map(|idx| self.positions[idx])
+    }
+
+    fn grid_shape(size: usize) -> Option<(usize, usize)> {
+        (2..=size.isqrt())
+            .rev()
+            .find(|&rows| size.is_multiple_of(rows))
+            .map(|rows| (rows, size / rows))
+    }
+}
+
+impl Drop for PolybiusSquare {
+    fn drop(&mut self) {
+        for row in &mut self.matrix {
+            row.fill(0);
+        }
+        self.positions.fill((0, 0));
+        // Overwrite the key's bytes in place before letting go of them
+        let len = self.key.len();
+        self.key.clear();
+        self.key.extend(std::iter::repeat_n('\0', len));
+        self.key.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polybius_square() {
+        let square =
+            PolybiusSquare::new("playfair example", Alphabet::english_merged_ij()).unwrap();
+        assert_eq!(square.key(), "playfirexmbcdghknoqstuvwz");
+        assert_eq!((square.rows(), square.cols()), (5, 5));
+        assert_eq!(square.symbol_at(1, 0), 'i');
+        assert_eq!(square.coordinates('J'), Some((1, 0)));
+        assert_eq!(square.coordinates('1'), None);
+
+        let e = Alphabet::english_merged_ij().index_of('e').unwrap();
+        assert_eq!(square.position(e), (1, 2));
+        assert_eq!(square.index_at(1, 2), e);
+    }
+
+    #[test]
+    fn test_polybius_square_shapes() {
+        let square = PolybiusSquare::new("", Alphabet::english()).unwrap();
+        assert_eq!((square.rows(), square.cols()), (2, 13));
+
+        let digits = Alphabet::new("abcdefghijklmnopqrstuvwxyz0123456789").unwrap();
+        let square = PolybiusSquare::new("zebra", digits.clone()).unwrap();
+        assert_eq!((square.rows(), square.cols()), (6, 6));
+
+        let square = PolybiusSquare::with_shape("", digits.clone(), 4, 9).unwrap();
+        assert_eq!(square.symbol_at(1, 0), 'j');
+        assert!(PolybiusSquare::with_shape("", digits, 5, 5).is_err());
+        assert!(PolybiusSquare::new("", Alphabet::new("abcdefghijklm").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use crate::error::PolygraphiaError;
+use crate::utils::Alphabet;
+
+/// Puts processed letters back into the letter slots of `template`; leftovers are appended.
+pub(crate) fn restore_layout(template: &str, letters: &[char], alphabet: &Alphabet) -> String {
+    let mut result = String::with_capacity(template.len() + letters.len());
+    restore_layout_into(template, letters, alphabet, &mut result);
+    result
+}
+
+/// Like `restore_layout`, appending to `out`.
+pub(crate) fn restore_layout_into(
+    template: &str,
+    letters: &[char],
+    alphabet: &Alphabet,
+    out: &mut String,
+) {
+    let mut letters = letters.iter();
+    for c in template.chars() {
+        if !alphabet.contains(c) {
+            out.push(c);
+        } else if let Some(&letter) = letters.next() {
+            out.push(letter);
+        }
+    }
+    out.extend(letters);
+}
+
+/// Layout of a streamed text still waiting to be filled: `restore_layout` one
+/// piece at a time.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Layout {
+    /// Template still waiting for letters, starting at an empty letter slot.
+    template: String,
+    /// Processed letters still waiting for a slot.
+    letters: VecDeque<char>,
+}
+
+impl Layout {
+    /// Lays `letters` out over the letter slots of `template` as
+    /// `restore_layout` would over the whole text, holding back whatever has
+    /// to wait for the next piece.
+    pub(crate) fn lay_out(
+        &mut self,
+        template: &str,
+        letters: &[char],
+        alphabet: &Alphabet,
+        out: &mut String,
+    ) {
+        self.template.push_str(template);
+        self.letters.extend(letters);
+        let mut used = self.template.len();
+        for (i, c) in self.template.char_indices() {
+            if !alphabet.contains(c) {
+                out.push(c);
+            } else if let Some(letter) = self.letters.pop_front() {
+                out.push(letter);
+            } else {
+                used = i;
+                break;
+            }
+        }
+        self.template.drain(..used);
+    }
+
+    /// Lays out the last piece: unfilled slots are dropped and leftover letters
+    /// appended.
+    pub(crate) fn finish(
+        &mut self,
+        template: &str,
+        letters: &[char],
+        alphabet: &Alphabet,
+        out: &mut String,
+    ) {
+        self.lay_out(template, letters, alphabet, out);
+        out.extend(self.template.chars().filter(|&c| !alphabet.contains(c)));
+        out.extend(self.letters.drain(..));
+        self.template.clear();
+    }
+}
+
+/// Replaces every character of the UTF-8 text in `data` in place, without
+/// allocating, for ciphers that map one character to one character; `None`
+/// drops it. `start` makes a fresh mapping for each of two passes: the first
+/// checks that every replacement has the byte length of what it replaces, so
+/// that `data` is left untouched on failure, and the second writes them.
+pub(crate) fn map_in_place<F>(
+    data: &mut [u8],
+    name: &str,
+    what: &str,
+    mut start: impl FnMut() -> F,
+) -> Result<(), PolygraphiaError>
+where
+    F: FnMut(char) -> Option<char>,
+{
+    let text = std::str::from_utf8(data)
+        .map_err(|e| PolygraphiaError::InvalidInput(format!("Data is not valid UTF-8: {e}")))?;
+    if text.is_empty() {
+        return Err(PolygraphiaError::InvalidInput(format!(
+            "{what} cannot be empty"
+        )));
+    }
+    let mut map = start();
+    let mut length = 0;
+    let mut aligned = true;
+    for c in text.chars() {
+        let width = map(c).map_or(0, char::len_utf8);
+        aligned &= width == c.len_utf8();
+        length += width;
+    }
+    if !aligned {
+        return Err(PolygraphiaError::InvalidInput(format!(
+            "{name} changed the length of the text from {} to {length} bytes",
+            data.len()
+        )));
+    }
+
+    let mut map = start();
+    let mut i = 0;
+    while i < data.len() {
+        let width = match data[i] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            _ => 4,
+        };
+        let slot = &mut data[i..i + width];
+        let c = std::str::from_utf8(slot)
+            .ok()
+            .and_then(|s| s.chars().next())
+            .expect("validated above");
+        if let Some(mapped) = map(c) {
+            mapped.encode_utf8(slot);
+        }
+        i += width;
+    }
+    Ok(())
+}
+
+/// Reorders `letters` so that position `k` takes `letters[order[k]]`, or undoes
+/// that reordering when `forward` is false. `order` must be a permutation.
+pub(crate) fn transpose(letters: &[char], order: &[usize], forward: bool) -> Vec<char> {
+    if forward {
+        order.iter().map(|&from| letters[from]).collect()
+    } else {
+        let mut result = vec!['\0'; letters.len()];
+        for (&to, &c) in order.iter().zip(letters) {
+            result[to] = c;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_layout() {
+        let alphabet = Alphabet::english();
+        assert_eq!(
+            restore_layout("ab, c!", &['x', 'y', 'z'], &alphabet),
+            "xy, z!"
+        );
+
+        // Extra letters are appended
+        assert_eq!(
+            restore_layout("ab c", &['w', 'x', 'y', 'z'], &alphabet),
+            "wx yz"
+        );
+        assert_eq!(restore_layout("ab!", &['x', 'y', 'z'], &alphabet), "xy!z");
+
+        // Missing letters drop their slots
+        assert_eq!(
+            restore_layout("ab cd.", &['x', 'y', 'z'], &alphabet),
+            "xy z."
+        );
+    }
+
+    #[test]
+    fn test_layout_in_pieces() {
+        let alphabet = Alphabet::english();
+        let mut layout = Layout::default();
+        let mut out = String::new();
+        layout.lay_out("ab, c", &['w', 'x', 'y', 'z'], &alphabet, &mut out);
+        assert_eq!(out, "wx, y");
+        layout.lay_out("de f", &['v'], &alphabet, &mut out);
+        assert_eq!(out, "wx, yzv ");
+        layout.finish("g!", &['u', 't', 's'], &alphabet, &mut out);
+        assert_eq!(out, "wx, yzv ut!s");
+    }
+
+    #[test]
+    fn test_transpose() {
+        let letters = ['a', 'b', 'c', 'd'];
+        let order = [2, 0, 3, 1];
+        let moved = transpose(&letters, &order, true);
+        assert_eq!(moved, ['c', 'a', 'd', 'b']);
+        assert_eq!(transpose(&moved, &order, false), letters);
+    }
+
+    #[test]
+    fn test_restore_layout_custom_alphabet() {
+        let alphabet = Alphabet::new("abc ").unwrap();
+        assert_eq!(restore_layout("a b!", &['c', 'a', ' '], &alphabet), "ca !");
+    }
+}